@@ -66,7 +66,7 @@ fn setup(
     info!("  R - Reset to defaults");
     info!("  ESC - Exit");
 
-    commands.spawn(Camera2d::default());
+    commands.spawn(Camera2d);
 }
 
 fn handle_input(
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use bevy_settings::{
+    prelude::*,
+    semver::{Version, VersionReq},
+    Settings, SettingsError, VersionedMigrationFn,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Audio settings at schema version 3.0.0
+///
+/// - Version 1 stored `volume` as a percentage
+/// - Version 2 stored `volume` as a fraction
+/// - Version 3 renamed `volume` to `master`
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(migrations = "audio_migrations")]
+struct AudioSettings {
+    master: f32,
+    muted: bool,
+}
+
+fn percent_to_fraction(
+    _from: &Version,
+    _range: &VersionReq,
+    delta: &mut Value,
+) -> Result<bool, SettingsError> {
+    let Some(volume) = delta.get("volume").and_then(Value::as_f64) else {
+        return Ok(false);
+    };
+    delta["volume"] = (volume / 100.0).into();
+    Ok(true)
+}
+
+fn rename_volume(
+    _from: &Version,
+    _range: &VersionReq,
+    delta: &mut Value,
+) -> Result<bool, SettingsError> {
+    match delta
+        .as_object_mut()
+        .and_then(|delta| delta.remove("volume"))
+    {
+        Some(volume) => {
+            delta["master"] = volume;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Migrations by the range of stored versions they handle, chained in ascending order
+fn audio_migrations() -> Vec<(VersionReq, VersionedMigrationFn)> {
+    vec![
+        (
+            VersionReq::parse(">=1.0.0, <2.0.0").unwrap(),
+            percent_to_fraction,
+        ),
+        (VersionReq::parse(">=2.0.0, <3.0.0").unwrap(), rename_volume),
+    ]
+}
+
+/// Fallback for versions none of the ranges match, e.g. files saved before versioning
+fn migrate_unversioned(_from: &Version, _delta: &mut Value) -> Result<bool, SettingsError> {
+    Ok(false)
+}
+
+fn main() {
+    // A settings file written by version 1 of the app
+    let base_path = std::env::temp_dir().join("bevy_settings_migration_example");
+    std::fs::create_dir_all(&base_path).unwrap();
+    std::fs::write(
+        base_path.join("AudioSettings.json"),
+        r#"{ "_versions": { "audiosettings": "1.2.0" }, "audiosettings": { "volume": 80 } }"#,
+    )
+    .unwrap();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("AudioSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(base_path.to_str().unwrap())
+            .register_with_version::<AudioSettings>("3.0.0", migrate_unversioned),
+    );
+    app.update();
+
+    // Migrated from 1.2.0 through 2.0.0 to 3.0.0: `master` is 0.8
+    println!("{:?}", app.world().resource::<AudioSettings>());
+    println!(
+        "{}",
+        std::fs::read_to_string(base_path.join("AudioSettings.json")).unwrap()
+    );
+}
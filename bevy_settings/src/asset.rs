@@ -0,0 +1,155 @@
+use crate::{
+    error::SettingsError,
+    migration::{MigrationLog, SettingsVersionInfo},
+    plugin::{insert_config, SettingsHandler, SettingsLoadPending},
+    storage::{get_type_key, merge_with_base, SettingsManager, SettingsTypeConfig},
+    Settings,
+};
+use bevy::asset::{io::Reader, AssetLoader, AssetPath, LoadContext, LoadState};
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+
+/// Settings bundled as a Bevy asset, holding the JSON contents of the file
+///
+/// Files with the `.settings.json` extension are loaded by [`JsonSettingsAssetLoader`], which
+/// is registered by [`SettingsPlugin`](crate::SettingsPlugin) when a type is registered with
+/// [`SettingsPlugin::register_as_asset`](crate::SettingsPlugin::register_as_asset).
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct JsonSettingsAsset(pub Value);
+
+/// Asset loader for [`JsonSettingsAsset`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSettingsAssetLoader;
+
+impl AssetLoader for JsonSettingsAssetLoader {
+    type Asset = JsonSettingsAsset;
+    type Settings = ();
+    type Error = SettingsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(JsonSettingsAsset(serde_json::from_slice(&bytes)?))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["settings.json"]
+    }
+}
+
+/// Handle of the asset a settings type is loaded from, removed once it was loaded
+#[derive(Resource)]
+struct SettingsAssetHandle<T: Settings> {
+    handle: Handle<JsonSettingsAsset>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+/// Settings handler that loads a settings type from an asset instead of the settings file
+pub(crate) struct AssetSettingsHandler<T: Settings> {
+    path: AssetPath<'static>,
+    config: SettingsTypeConfig<T>,
+}
+
+impl<T: Settings> AssetSettingsHandler<T> {
+    pub(crate) fn new(path: AssetPath<'static>, config: SettingsTypeConfig<T>) -> Self {
+        Self { path, config }
+    }
+}
+
+impl<T: Settings> SettingsHandler for AssetSettingsHandler<T> {
+    fn type_key(&self) -> String {
+        get_type_key::<T>()
+    }
+
+    fn schema(&self) -> Option<Value> {
+        T::schema()
+    }
+
+    fn is_lazy(&self) -> bool {
+        true
+    }
+
+    fn load_and_insert(
+        &self,
+        app: &mut App,
+        manager: &SettingsManager,
+        _all_settings: &Map<String, Value>,
+        _log: &mut MigrationLog,
+        _version_info: &mut SettingsVersionInfo,
+    ) -> bool {
+        insert_config(app, manager, &self.config);
+
+        let Some(asset_server) = app.world().get_resource::<AssetServer>().cloned() else {
+            warn!(
+                "Can't load settings for {} from {}, add the AssetPlugin before the SettingsPlugin. Using defaults.",
+                T::type_name(),
+                self.path
+            );
+            app.insert_resource((self.config.default_fn)());
+            return false;
+        };
+
+        if !app.world().contains_resource::<Assets<JsonSettingsAsset>>() {
+            app.init_asset::<JsonSettingsAsset>()
+                .init_asset_loader::<JsonSettingsAssetLoader>();
+        }
+
+        app.insert_resource(SettingsAssetHandle::<T> {
+            handle: asset_server.load(self.path.clone()),
+            _marker: std::marker::PhantomData,
+        });
+        app.init_resource::<SettingsLoadPending<T>>();
+        false
+    }
+
+    fn register_save_system(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            insert_settings_from_asset::<T>.run_if(resource_exists::<SettingsAssetHandle<T>>),
+        );
+    }
+}
+
+/// Insert the settings once their asset has finished loading, or defaults if it failed
+fn insert_settings_from_asset<T: Settings>(
+    mut commands: Commands,
+    source: Res<SettingsAssetHandle<T>>,
+    assets: Res<Assets<JsonSettingsAsset>>,
+    asset_server: Res<AssetServer>,
+    config: Res<SettingsTypeConfig<T>>,
+    manager: Res<SettingsManager>,
+) {
+    let settings = if let Some(asset) = assets.get(&source.handle) {
+        merge_with_base(
+            (config.default_fn)(),
+            Some(&asset.0),
+            manager.storage.merge_options,
+        )
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to merge settings asset for {}: {}. Using defaults.",
+                T::type_name(),
+                e
+            );
+            (config.default_fn)()
+        })
+    } else if let Some(LoadState::Failed(e)) = asset_server.get_load_state(&source.handle) {
+        warn!(
+            "Failed to load settings asset for {}: {}. Using defaults.",
+            T::type_name(),
+            e
+        );
+        (config.default_fn)()
+    } else {
+        return;
+    };
+
+    commands.insert_resource(settings);
+    commands.remove_resource::<SettingsLoadPending<T>>();
+    commands.remove_resource::<SettingsAssetHandle<T>>();
+}
@@ -0,0 +1,313 @@
+use crate::{
+    error::Result,
+    history::SettingsHistory,
+    storage::{
+        compute_delta_from_base, get_type_key, merge_with_base, MergeOptions, SettingsManager,
+        SettingsTypeConfig, StorageStats,
+    },
+    Settings,
+};
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+use std::fs;
+
+/// Extension methods for [`Commands`] to work with settings resources
+pub trait SettingsCommandsExt {
+    /// Copy the fields that `F` and `T` have in common from `F` into `T`
+    ///
+    /// Top-level fields are matched by name. Fields that only exist in one of the types are left
+    /// untouched. Nothing is copied if the values of the shared fields don't fit `T`.
+    fn copy_settings<F: Settings, T: Settings>(&mut self);
+
+    /// Reset `T` to its defaults and remove its section from the settings file
+    ///
+    /// The file itself is deleted once no other settings are stored in it. A subsequent load
+    /// returns the defaults.
+    fn delete_settings<T: Settings>(&mut self);
+
+    /// Save the current `T` as the profile `name`, replacing an existing profile
+    ///
+    /// Profiles are stored as `profiles/<name>/<section>.<extension>` in the settings
+    /// directory, using the configured format. See [`list_profiles`] for the saved profiles.
+    fn save_profile<T: Settings>(&mut self, name: &str);
+
+    /// Replace `T` with the profile `name`, merged over the defaults
+    ///
+    /// The loaded values are saved to the settings file like any other change. The profile
+    /// itself is left untouched.
+    fn load_profile<T: Settings>(&mut self, name: &str);
+
+    /// Delete the profile `name` of `T`
+    fn delete_profile<T: Settings>(&mut self, name: &str);
+
+    /// Restore the value of `T` before the last saved change
+    ///
+    /// Does nothing when there is nothing to undo. Requires
+    /// [`SettingsPlugin::with_history_depth`](crate::SettingsPlugin::with_history_depth).
+    fn undo_settings<T: Settings>(&mut self);
+
+    /// Restore the value of `T` undone last by [`undo_settings`](Self::undo_settings)
+    ///
+    /// Does nothing when there is nothing to redo. Any other change of `T` clears the values
+    /// to redo.
+    fn redo_settings<T: Settings>(&mut self);
+}
+
+impl SettingsCommandsExt for Commands<'_, '_> {
+    fn copy_settings<F: Settings, T: Settings>(&mut self) {
+        self.queue(copy_settings::<F, T>);
+    }
+
+    fn delete_settings<T: Settings>(&mut self) {
+        self.queue(delete_settings::<T>);
+    }
+
+    fn save_profile<T: Settings>(&mut self, name: &str) {
+        let name = name.to_string();
+        self.queue(move |world: &mut World| save_profile::<T>(world, &name));
+    }
+
+    fn load_profile<T: Settings>(&mut self, name: &str) {
+        let name = name.to_string();
+        self.queue(move |world: &mut World| load_profile::<T>(world, &name));
+    }
+
+    fn delete_profile<T: Settings>(&mut self, name: &str) {
+        let name = name.to_string();
+        self.queue(move |world: &mut World| delete_profile::<T>(world, &name));
+    }
+
+    fn undo_settings<T: Settings>(&mut self) {
+        self.queue(|world: &mut World| restore_from_history::<T>(world, SettingsHistory::undo));
+    }
+
+    fn redo_settings<T: Settings>(&mut self) {
+        self.queue(|world: &mut World| restore_from_history::<T>(world, SettingsHistory::redo));
+    }
+}
+
+/// Replace `T` with the value taken from its history, if any
+fn restore_from_history<T: Settings>(
+    world: &mut World,
+    take: fn(&mut SettingsHistory<T>, &T) -> Option<T>,
+) {
+    let Some(current) = world.get_resource::<T>().cloned() else {
+        return;
+    };
+    let Some(mut history) = world.get_resource_mut::<SettingsHistory<T>>() else {
+        warn!(
+            "Can't undo or redo {}, the settings history isn't enabled",
+            T::type_name()
+        );
+        return;
+    };
+
+    if let Some(value) = take(&mut history, &current) {
+        *world.resource_mut::<T>() = value;
+    }
+}
+
+/// List the names of the saved profiles of `T`, sorted by name
+///
+/// See [`SettingsCommandsExt::save_profile`].
+pub fn list_profiles<T: Settings>(world: &World) -> Vec<String> {
+    let Some(manager) = world.get_resource::<SettingsManager>() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(manager.storage.get_profiles_dir()) else {
+        return Vec::new();
+    };
+
+    let section = get_type_key::<T>();
+    let mut profiles: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| manager.storage.get_profile_path(name, &section).is_file())
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+/// Check that a profile name can be used as a directory name
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+/// Get the manager and the configuration of `T`, warning if profiles can't be used
+fn profile_context<'w, T: Settings>(
+    world: &'w World,
+    name: &str,
+) -> Option<(&'w SettingsManager, &'w SettingsTypeConfig<T>)> {
+    if !is_valid_profile_name(name) {
+        warn!("Invalid settings profile name '{}'", name);
+        return None;
+    }
+    match (
+        world.get_resource::<SettingsManager>(),
+        world.get_resource::<SettingsTypeConfig<T>>(),
+    ) {
+        (Some(manager), Some(config)) => Some((manager, config)),
+        _ => {
+            warn!(
+                "Can't use settings profiles for {}, the type isn't registered",
+                T::type_name()
+            );
+            None
+        }
+    }
+}
+
+fn save_profile<T: Settings>(world: &mut World, name: &str) {
+    let Some((manager, config)) = profile_context::<T>(world, name) else {
+        return;
+    };
+    let Some(settings) = world.get_resource::<T>() else {
+        warn!(
+            "Can't save settings profile '{}' for {}, the settings aren't loaded",
+            name,
+            T::type_name()
+        );
+        return;
+    };
+
+    let delta = compute_delta_from_base(settings, &(config.default_fn)())
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    let path = manager.storage.get_profile_path(name, &get_type_key::<T>());
+    let result: Result<()> = (|| {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        manager
+            .storage
+            .write_atomic(&path, &manager.storage.encode_value(&delta)?)
+    })();
+    if let Err(e) = result {
+        error!(
+            "Failed to save settings profile '{}' for {}: {}",
+            name,
+            T::type_name(),
+            e
+        );
+    }
+}
+
+fn load_profile<T: Settings>(world: &mut World, name: &str) {
+    let Some((manager, config)) = profile_context::<T>(world, name) else {
+        return;
+    };
+
+    let path = manager.storage.get_profile_path(name, &get_type_key::<T>());
+    let result = fs::read(&path)
+        .map_err(Into::into)
+        .and_then(|content| manager.storage.decode_value(&content))
+        .and_then(|delta| {
+            merge_with_base(
+                (config.default_fn)(),
+                Some(&delta),
+                manager.storage.merge_options,
+            )
+        });
+    match result {
+        Ok(settings) => world.insert_resource(settings),
+        Err(e) => warn!(
+            "Failed to load settings profile '{}' for {}: {}",
+            name,
+            T::type_name(),
+            e
+        ),
+    }
+}
+
+fn delete_profile<T: Settings>(world: &mut World, name: &str) {
+    let Some((manager, _)) = profile_context::<T>(world, name) else {
+        return;
+    };
+
+    let path = manager.storage.get_profile_path(name, &get_type_key::<T>());
+    if let Err(e) = fs::remove_file(&path) {
+        warn!(
+            "Failed to delete settings profile '{}' for {}: {}",
+            name,
+            T::type_name(),
+            e
+        );
+        return;
+    }
+    // Only succeeds once no other settings type has a file in the profile
+    if let Some(parent) = path.parent() {
+        let _ = fs::remove_dir(parent);
+    }
+}
+
+fn copy_settings<F: Settings, T: Settings>(world: &mut World) {
+    let (Some(from), Some(to)) = (world.get_resource::<F>(), world.get_resource::<T>()) else {
+        warn!(
+            "Can't copy settings from {} to {}, both must be loaded",
+            F::type_name(),
+            T::type_name()
+        );
+        return;
+    };
+
+    let to = to.clone();
+    let (Ok(Value::Object(from_map)), Ok(Value::Object(to_map))) =
+        (serde_json::to_value(from), serde_json::to_value(&to))
+    else {
+        return;
+    };
+
+    let shared: Map<String, Value> = from_map
+        .into_iter()
+        .filter(|(key, _)| to_map.contains_key(key))
+        .collect();
+
+    let options = world
+        .get_resource::<SettingsManager>()
+        .map_or_else(MergeOptions::default, |manager| {
+            manager.storage.merge_options
+        });
+
+    match merge_with_base(to.clone(), Some(&Value::Object(shared)), options) {
+        Ok(copied) => {
+            // Only touch the resource if something changed, so it isn't saved needlessly
+            if copied != to {
+                world.insert_resource(copied);
+            }
+        }
+        Err(e) => warn!(
+            "Failed to copy settings from {} to {}: {}",
+            F::type_name(),
+            T::type_name(),
+            e
+        ),
+    }
+}
+
+fn delete_settings<T: Settings>(world: &mut World) {
+    let Some(config) = world.get_resource::<SettingsTypeConfig<T>>() else {
+        warn!(
+            "Can't delete settings for {}, the type isn't registered",
+            T::type_name()
+        );
+        return;
+    };
+    let defaults = (config.default_fn)();
+
+    // The save system notices the reset and removes the section from the file
+    if let Some(mut settings) = world.get_resource_mut::<T>() {
+        *settings = defaults;
+        return;
+    }
+
+    // Not loaded right now (lazy or state-scoped), so remove the section directly
+    world.resource_scope(|world, mut stats: Mut<StorageStats>| {
+        let manager = world.resource::<SettingsManager>();
+        manager
+            .settings_map
+            .lock()
+            .unwrap()
+            .remove(&get_type_key::<T>());
+        if let Err(e) = manager.save(&mut stats) {
+            error!("Failed to delete settings for {}: {}", T::type_name(), e);
+        }
+    });
+}
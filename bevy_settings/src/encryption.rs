@@ -0,0 +1,103 @@
+use crate::error::{Result, SettingsError};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use sha2::Sha256;
+
+/// Extension appended to the settings file name when the file is encrypted
+pub(crate) const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// Length of the random nonce stored in front of the ciphertext
+pub(crate) const NONCE_LEN: usize = 12;
+
+/// PBKDF2-HMAC-SHA256 iterations for keys derived from a passphrase
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+/// Derive a 256-bit key from a passphrase with PBKDF2-HMAC-SHA256
+///
+/// The salt is derived from the settings name, so the key is the same on every start without
+/// storing anything next to the file.
+pub(crate) fn derive_key(passphrase: &str, name: &str) -> [u8; 32] {
+    derive_key_with_rounds(passphrase, name, PBKDF2_ROUNDS)
+}
+
+fn derive_key_with_rounds(passphrase: &str, name: &str, rounds: u32) -> [u8; 32] {
+    let salt = format!("bevy_settings:{}", name);
+    pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(passphrase.as_bytes(), salt.as_bytes(), rounds)
+}
+
+/// Encrypt `content` with AES-256-GCM, prepending the random nonce to the ciphertext
+pub(crate) fn encrypt(key: &[u8; 32], content: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, content)
+        .map_err(|e| SettingsError::EncryptionFailed(e.to_string()))?;
+
+    let mut encrypted = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypt content written by [`encrypt`]
+///
+/// Fails for a wrong key and for files that were modified or truncated.
+pub(crate) fn decrypt(key: &[u8; 32], content: &[u8]) -> Result<Vec<u8>> {
+    if content.len() < NONCE_LEN {
+        return Err(SettingsError::DecryptionFailed(
+            "file is too short to contain a nonce".to_string(),
+        ));
+    }
+
+    let (nonce, ciphertext) = content.split_at(NONCE_LEN);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| SettingsError::DecryptionFailed("wrong key or modified file".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = [7; 32];
+        let encrypted = encrypt(&key, b"{\"volume\": 0.5}").unwrap();
+
+        assert_ne!(&encrypted[NONCE_LEN..], b"{\"volume\": 0.5}");
+        assert_eq!(decrypt(&key, &encrypted).unwrap(), b"{\"volume\": 0.5}");
+        // Every write uses a new nonce
+        assert_ne!(encrypted, encrypt(&key, b"{\"volume\": 0.5}").unwrap());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let mut encrypted = encrypt(&[7; 32], b"settings").unwrap();
+
+        assert!(matches!(
+            decrypt(&[8; 32], &encrypted),
+            Err(SettingsError::DecryptionFailed(_))
+        ));
+        *encrypted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decrypt(&[7; 32], &encrypted),
+            Err(SettingsError::DecryptionFailed(_))
+        ));
+        assert!(matches!(
+            decrypt(&[7; 32], &encrypted[..4]),
+            Err(SettingsError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_derive_key_depends_on_name() {
+        // The full number of rounds takes seconds in debug builds
+        let derive = |passphrase, name| derive_key_with_rounds(passphrase, name, 1_000);
+
+        assert_eq!(derive("secret", "Game"), derive("secret", "Game"));
+        assert_ne!(derive("secret", "Game"), derive("secret", "Other"));
+        assert_ne!(derive("secret", "Game"), derive("other", "Game"));
+    }
+}
@@ -7,6 +7,23 @@ pub enum SettingsError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// Error during YAML serialization/deserialization
+    #[cfg(feature = "yaml")]
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    /// Error during TOML serialization
+    #[error("TOML serialization error: {0}")]
+    TomlEncode(#[from] toml::ser::Error),
+
+    /// Error during TOML deserialization
+    #[error("TOML deserialization error: {0}")]
+    TomlDecode(#[from] toml::de::Error),
+
+    /// Settings file is not valid UTF-8
+    #[error("Invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+
     /// Error during binary serialization
     #[error("Binary serialization error: {0}")]
     BincodeEncode(#[from] bincode::error::EncodeError),
@@ -26,6 +43,22 @@ pub enum SettingsError {
     /// Error comparing settings with defaults
     #[error("Failed to compare settings with defaults")]
     ComparisonFailed,
+
+    /// Settings file is larger than the configured limit
+    #[error("Settings file is too large ({size} bytes, limit is {limit} bytes)")]
+    FileTooLarge { size: u64, limit: u64 },
+
+    /// Error while migrating settings from an older schema version
+    #[error("Migration error: {0}")]
+    Migration(String),
+
+    /// Encrypted settings file can't be decrypted, e.g. because of a wrong key
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+
+    /// Error while encrypting the settings file
+    #[error("Encryption failed: {0}")]
+    EncryptionFailed(String),
 }
 
 pub type Result<T> = std::result::Result<T, SettingsError>;
@@ -1,3 +1,7 @@
+use serde::de::{self, DeserializeOwned};
+use serde::Serialize;
+use serde_json::Value;
+
 /// Serialization format for settings storage
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SerializationFormat {
@@ -5,6 +9,16 @@ pub enum SerializationFormat {
     Json,
     /// Binary format using bincode - compact and efficient
     Binary,
+    /// YAML format - human readable, supports comments written by hand
+    ///
+    /// Requires the `yaml` feature.
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// TOML format - human readable, supports comments written by hand
+    ///
+    /// TOML has no null value, so `null` in the delta, like an `Option` that is `None` while its
+    /// default is `Some`, is written as a `{ _null = true }` table and loaded as `null` again.
+    Toml,
 }
 
 impl SerializationFormat {
@@ -12,7 +26,561 @@ impl SerializationFormat {
     pub fn extension(&self) -> &'static str {
         match self {
             SerializationFormat::Json => "json",
-            SerializationFormat::Binary => "",
+            SerializationFormat::Binary => "bin",
+            #[cfg(feature = "yaml")]
+            SerializationFormat::Yaml => "yaml",
+            SerializationFormat::Toml => "toml",
+        }
+    }
+}
+
+/// Get the recommended serialization format for the target platform
+///
+/// - [`SerializationFormat::Binary`] on WebAssembly (`target_arch = "wasm32"`) and console
+///   targets (`target_os = "horizon"`, `"vita"` and `"psp"`), where storage is limited and
+///   files aren't edited by hand
+/// - [`SerializationFormat::Json`] everywhere else, including Windows, Linux and macOS
+pub fn platform_default_format() -> SerializationFormat {
+    if cfg!(any(
+        target_arch = "wasm32",
+        target_os = "horizon",
+        target_os = "vita",
+        target_os = "psp"
+    )) {
+        SerializationFormat::Binary
+    } else {
+        SerializationFormat::Json
+    }
+}
+
+/// Order of the object keys in written JSON files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonSortMode {
+    /// Keep the order of the fields, sections are ordered by the
+    /// [`SectionSortOrder`](crate::SectionSortOrder)
+    #[default]
+    None,
+    /// Sort the keys of all objects by name, at every nesting level
+    Alphabetical,
+}
+
+/// Sort the keys of all objects in `value` by name, recursively
+pub(crate) fn sort_json_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_json_value);
+        }
+        Value::Array(values) => values.iter_mut().for_each(sort_json_value),
+        _ => {}
+    }
+}
+
+/// Text encoding of JSON settings files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileEncoding {
+    /// UTF-8 without a byte order mark
+    #[default]
+    Utf8,
+    /// UTF-16 little endian with a byte order mark, common on Windows
+    Utf16Le,
+    /// UTF-16 big endian with a byte order mark
+    Utf16Be,
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+impl FileEncoding {
+    /// Convert UTF-8 text to this encoding
+    pub(crate) fn encode(self, text: &str) -> Vec<u8> {
+        let (bom, to_bytes): (_, fn(u16) -> [u8; 2]) = match self {
+            FileEncoding::Utf8 => return text.as_bytes().to_vec(),
+            FileEncoding::Utf16Le => (UTF16_LE_BOM, u16::to_le_bytes),
+            FileEncoding::Utf16Be => (UTF16_BE_BOM, u16::to_be_bytes),
+        };
+        bom.into_iter()
+            .chain(text.encode_utf16().flat_map(to_bytes))
+            .collect()
+    }
+
+    /// Convert file contents to UTF-8
+    ///
+    /// The encoding is detected from the byte order mark, files without one are read with this
+    /// encoding.
+    pub(crate) fn decode(self, content: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        let (content, encoding) = if let Some(rest) = content.strip_prefix(&UTF8_BOM) {
+            (rest, FileEncoding::Utf8)
+        } else if let Some(rest) = content.strip_prefix(&UTF16_LE_BOM) {
+            (rest, FileEncoding::Utf16Le)
+        } else if let Some(rest) = content.strip_prefix(&UTF16_BE_BOM) {
+            (rest, FileEncoding::Utf16Be)
+        } else {
+            (content.as_slice(), self)
+        };
+
+        let from_bytes: fn([u8; 2]) -> u16 = match encoding {
+            FileEncoding::Utf8 => return Ok(content.to_vec()),
+            FileEncoding::Utf16Le => u16::from_le_bytes,
+            FileEncoding::Utf16Be => u16::from_be_bytes,
+        };
+        let (units, rest) = content.as_chunks::<2>();
+        if !rest.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "UTF-16 file has an odd number of bytes",
+            ));
+        }
+        let units: Vec<u16> = units.iter().map(|unit| from_bytes(*unit)).collect();
+        String::from_utf16(&units)
+            .map(String::into_bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Precision used when parsing floating point numbers from JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatParseMode {
+    /// Round every float to `f32` precision, including `f64` fields
+    F32,
+    /// Parse floats with the precision of the field they are loaded into
+    #[default]
+    F64,
+}
+
+/// Options for decoding floats from JSON settings files
+///
+/// JSON has no literals for infinity and NaN, `serde_json` writes them as `null`, which never
+/// loads into a float field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Precision used when parsing floats
+    pub float_parse_mode: FloatParseMode,
+    /// Reject numbers that overflow an `f32` field instead of loading them as infinity
+    pub reject_inf: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            float_parse_mode: FloatParseMode::F64,
+            reject_inf: true,
+        }
+    }
+}
+
+/// Serde helpers that store `u128` and `i128` fields as JSON strings
+///
+/// JSON numbers can't hold the full range of 128-bit integers, so serializing `u128::MAX` to a
+/// [`Value`] fails. Use with `#[serde(with = "bevy_settings::big_int")]`, and add
+/// `#[settings(big_int)]` when deriving a schema, so the field is described as a string. Plain
+/// JSON numbers are still accepted when loading.
+///
+/// # Example
+/// ```
+/// # use bevy::prelude::Resource;
+/// # use bevy_settings::Settings;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq)]
+/// #[settings(schema)]
+/// struct StatsSettings {
+///     #[serde(with = "bevy_settings::big_int")]
+///     #[settings(big_int)]
+///     high_score: u128,
+/// }
+/// ```
+pub mod big_int {
+    use serde::de::{self, Deserializer, Unexpected, Visitor};
+    use serde::Serializer;
+    use std::fmt::{self, Display};
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    /// Integer types that are stored as strings, implemented for `u128` and `i128`
+    pub trait BigInt: Display + FromStr + From<u64> + TryFrom<i64> {}
+
+    impl BigInt for u128 {}
+    impl BigInt for i128 {}
+
+    /// Serialize the integer as a decimal string
+    pub fn serialize<T: BigInt, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    /// Deserialize the integer from a decimal string or a JSON number
+    pub fn deserialize<'de, T: BigInt, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        deserializer.deserialize_any(BigIntVisitor(PhantomData))
+    }
+
+    struct BigIntVisitor<T>(PhantomData<T>);
+
+    impl<T: BigInt> Visitor<'_> for BigIntVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an integer or a string containing an integer")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+            Ok(T::from(value))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+            T::try_from(value).map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+            value
+                .parse()
+                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+        }
+    }
+}
+
+/// Store a [`Duration`](std::time::Duration) as a human-readable string like `"500ms"`
+///
+/// Use with `#[serde(with = "bevy_settings::human_duration")]` on a `Duration` field instead of
+/// the verbose `{"secs": 5, "nanos": 0}`. Mark the field with
+/// `#[settings(duration_format = "human")]` when deriving a schema, so the field is described as
+/// a string. Strings are parsed with [`humantime`], so `"1h30m"` and `"1h 30m"` both work, and
+/// the object form is still accepted when loading.
+///
+/// # Example
+/// ```
+/// # use bevy::prelude::Resource;
+/// # use bevy_settings::Settings;
+/// # use serde::{Deserialize, Serialize};
+/// # use std::time::Duration;
+/// #[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq)]
+/// #[settings(schema)]
+/// struct NetworkSettings {
+///     #[serde(with = "bevy_settings::human_duration")]
+///     #[settings(duration_format = "human")]
+///     timeout: Duration,
+/// }
+/// ```
+pub mod human_duration {
+    use serde::de::{
+        self, value::MapAccessDeserializer, Deserializer, MapAccess, Unexpected, Visitor,
+    };
+    use serde::{Deserialize, Serializer};
+    use std::fmt;
+    use std::time::Duration;
+
+    /// Serialize the duration as a string like `"1h30m"`
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let formatted = humantime::format_duration(*value).to_string();
+        serializer.serialize_str(&formatted.replace(' ', ""))
+    }
+
+    /// Deserialize the duration from a string like `"5s"` or a `{"secs", "nanos"}` object
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+
+    impl<'de> Visitor<'de> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a duration like \"5s\" or \"1h30m\"")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+            humantime::parse_duration(value)
+                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Duration, A::Error> {
+            Duration::deserialize(MapAccessDeserializer::new(map))
+        }
+    }
+}
+
+/// Deserialize a JSON value, applying `options` to every float field
+pub(crate) fn from_value_with_options<T: DeserializeOwned + Serialize>(
+    mut value: Value,
+    options: JsonOptions,
+) -> Result<T, serde_json::Error> {
+    if options.float_parse_mode == FloatParseMode::F32 {
+        round_floats(&mut value, options.reject_inf)?;
+    }
+    if !options.reject_inf {
+        return serde_json::from_value(value);
+    }
+
+    let result = serde_json::from_value(value.clone())?;
+    // A number that fits into an `f64` can still overflow an `f32` field, which `serde_json`
+    // turns into infinity. Infinity is serialized as `null`, so compare with the input.
+    if let Ok(output) = serde_json::to_value(&result) {
+        check_overflow(&value, &output)?;
+    }
+    Ok(result)
+}
+
+/// Round every float in a JSON value to `f32` precision
+fn round_floats(value: &mut Value, reject_inf: bool) -> Result<(), serde_json::Error> {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            let rounded = number.as_f64().unwrap_or_default() as f32 as f64;
+            match serde_json::Number::from_f64(rounded) {
+                Some(rounded) => *number = rounded,
+                None if reject_inf => return Err(out_of_range(number)),
+                None => {}
+            }
+        }
+        Value::Array(array) => {
+            for element in array {
+                round_floats(element, reject_inf)?;
+            }
+        }
+        Value::Object(map) => {
+            for element in map.values_mut() {
+                round_floats(element, reject_inf)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Find numbers in `input` that were deserialized into an infinite float
+fn check_overflow(input: &Value, output: &Value) -> Result<(), serde_json::Error> {
+    match (input, output) {
+        (Value::Number(number), Value::Null) => Err(out_of_range(number)),
+        (Value::Array(input), Value::Array(output)) => input
+            .iter()
+            .zip(output)
+            .try_for_each(|(input, output)| check_overflow(input, output)),
+        (Value::Object(input), Value::Object(output)) => {
+            input
+                .iter()
+                .try_for_each(|(key, input)| match output.get(key) {
+                    Some(output) => check_overflow(input, output),
+                    None => Ok(()),
+                })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn out_of_range(number: &serde_json::Number) -> serde_json::Error {
+    de::Error::custom(format!("float {} is out of range", number))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct BigIntFields {
+        #[serde(with = "big_int")]
+        unsigned: u128,
+        #[serde(with = "big_int")]
+        signed: i128,
+    }
+
+    #[test]
+    fn test_big_int_round_trip() {
+        let fields = BigIntFields {
+            unsigned: u128::MAX,
+            signed: i128::MIN,
+        };
+        let value = serde_json::to_value(&fields).unwrap();
+        assert_eq!(value["unsigned"], u128::MAX.to_string());
+        assert_eq!(value["signed"], i128::MIN.to_string());
+
+        let parsed: BigIntFields = from_value_with_options(value, JsonOptions::default()).unwrap();
+        assert_eq!(parsed, fields);
+    }
+
+    #[test]
+    fn test_big_int_accepts_numbers() {
+        let value = serde_json::json!({ "unsigned": 5, "signed": -5 });
+        let parsed: BigIntFields = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            parsed,
+            BigIntFields {
+                unsigned: 5,
+                signed: -5
+            }
+        );
+
+        let value = serde_json::json!({ "unsigned": -5, "signed": 0 });
+        assert!(serde_json::from_value::<BigIntFields>(value).is_err());
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct DurationField {
+        #[serde(with = "human_duration")]
+        delay: Duration,
+    }
+
+    #[test]
+    fn test_human_duration() {
+        let parsed: DurationField =
+            serde_json::from_value(serde_json::json!({ "delay": "1s" })).unwrap();
+        assert_eq!(parsed.delay, Duration::from_secs(1));
+
+        let field = DurationField {
+            delay: Duration::from_millis(500),
+        };
+        assert_eq!(serde_json::to_value(&field).unwrap()["delay"], "500ms");
+
+        let field = DurationField {
+            delay: Duration::from_secs(5400),
+        };
+        let value = serde_json::to_value(&field).unwrap();
+        assert_eq!(value["delay"], "1h30m");
+        assert_eq!(
+            serde_json::from_value::<DurationField>(value).unwrap(),
+            field
+        );
+    }
+
+    #[test]
+    fn test_human_duration_accepts_objects() {
+        let value = serde_json::json!({ "delay": { "secs": 2, "nanos": 0 } });
+        let parsed: DurationField = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.delay, Duration::from_secs(2));
+
+        let value = serde_json::json!({ "delay": "soon" });
+        assert!(serde_json::from_value::<DurationField>(value).is_err());
+    }
+
+    #[test]
+    fn test_sort_json_value() {
+        let mut value = serde_json::json!({
+            "b": { "z": 1, "a": [{ "y": 2, "x": 3 }] },
+            "a": 4,
+        });
+        sort_json_value(&mut value);
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"a":4,"b":{"a":[{"x":3,"y":2}],"z":1}}"#
+        );
+    }
+
+    #[test]
+    fn test_file_encoding_round_trip() {
+        let text = r#"{"name":"Grüße, 世界"}"#;
+        for encoding in [
+            FileEncoding::Utf8,
+            FileEncoding::Utf16Le,
+            FileEncoding::Utf16Be,
+        ] {
+            let encoded = encoding.encode(text);
+            // The byte order mark takes precedence over the configured encoding
+            let decoded = FileEncoding::Utf8.decode(encoded.clone()).unwrap();
+            assert_eq!(String::from_utf8(decoded).unwrap(), text, "{:?}", encoding);
+            let decoded = encoding.decode(encoded).unwrap();
+            assert_eq!(String::from_utf8(decoded).unwrap(), text, "{:?}", encoding);
         }
     }
+
+    #[test]
+    fn test_file_encoding_without_bom() {
+        let text = "{}";
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let decoded = FileEncoding::Utf16Le.decode(utf16).unwrap();
+        assert_eq!(decoded, text.as_bytes());
+
+        let mut utf8 = UTF8_BOM.to_vec();
+        utf8.extend_from_slice(text.as_bytes());
+        assert_eq!(FileEncoding::Utf8.decode(utf8).unwrap(), text.as_bytes());
+
+        assert!(FileEncoding::Utf16Be.decode(vec![0x00]).is_err());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    fn test_platform_default_format_on_desktop() {
+        assert_eq!(platform_default_format(), SerializationFormat::Json);
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Off,
+        Fixed(f32),
+        Range { min: f64, max: f64 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Nested {
+        mode: Mode,
+        weights: HashMap<u32, f32>,
+        flags: HashMap<bool, f32>,
+        scale: Option<f64>,
+    }
+
+    #[test]
+    fn test_from_value_with_options_matches_serde_json() {
+        let value = json!({
+            "mode": { "Range": { "min": 0.5, "max": 2 } },
+            "weights": { "1": 0.25, "7": 3 },
+            "flags": { "true": 1.5 },
+            "scale": null,
+        });
+
+        let expected: Nested = serde_json::from_value(value.clone()).unwrap();
+        let parsed: Nested = from_value_with_options(value, JsonOptions::default()).unwrap();
+        assert_eq!(parsed, expected);
+
+        let parsed: Mode = from_value_with_options(json!("Off"), JsonOptions::default()).unwrap();
+        assert_eq!(parsed, Mode::Off);
+    }
+
+    #[test]
+    fn test_reject_inf_and_nan() {
+        let options = JsonOptions::default();
+        assert!(from_value_with_options::<f32>(json!(null), options).is_err());
+        assert!(from_value_with_options::<f32>(json!("inf"), options).is_err());
+        // Fits into an f64, but overflows an f32
+        assert!(from_value_with_options::<f32>(json!(1e40), options).is_err());
+        assert!(from_value_with_options::<Mode>(json!({ "Fixed": 1e40 }), options).is_err());
+        assert_eq!(
+            from_value_with_options::<f64>(json!(1e40), options).unwrap(),
+            1e40
+        );
+
+        let options = JsonOptions {
+            reject_inf: false,
+            ..Default::default()
+        };
+        assert!(from_value_with_options::<f32>(json!(null), options).is_err());
+        assert_eq!(
+            from_value_with_options::<f32>(json!(-1e40), options).unwrap(),
+            f32::NEG_INFINITY
+        );
+        assert_eq!(
+            from_value_with_options::<Mode>(json!({ "Fixed": 1e40 }), options).unwrap(),
+            Mode::Fixed(f32::INFINITY)
+        );
+    }
+
+    #[test]
+    fn test_float_parse_mode() {
+        let mut options = JsonOptions::default();
+        assert_eq!(
+            from_value_with_options::<f64>(json!(0.1), options).unwrap(),
+            0.1
+        );
+
+        options.float_parse_mode = FloatParseMode::F32;
+        assert_eq!(
+            from_value_with_options::<f64>(json!(0.1), options).unwrap(),
+            0.1f32 as f64
+        );
+        assert!(from_value_with_options::<f64>(json!(1e40), options).is_err());
+    }
 }
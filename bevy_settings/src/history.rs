@@ -0,0 +1,149 @@
+use crate::Settings;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Undo and redo stacks of a settings type
+///
+/// Only inserted when the plugin is built with
+/// [`SettingsPlugin::with_history_depth`](crate::SettingsPlugin::with_history_depth). The
+/// previous value is recorded every time the settings are saved, and restored with
+/// [`SettingsCommandsExt::undo_settings`](crate::SettingsCommandsExt::undo_settings) and
+/// [`SettingsCommandsExt::redo_settings`](crate::SettingsCommandsExt::redo_settings). The
+/// history only lives in memory and is never written to disk.
+#[derive(Resource)]
+pub struct SettingsHistory<T: Settings> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    capacity: usize,
+    /// Value as of the last save, recorded on the next change
+    saved: Option<T>,
+}
+
+impl<T: Settings> SettingsHistory<T> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::with_capacity(capacity),
+            redo: Vec::new(),
+            capacity,
+            saved: None,
+        }
+    }
+
+    /// Maximum number of values kept for undo
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Whether there is a value to undo to
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there is an undone value to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget all recorded values
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Start tracking from `value`, e.g. after the settings were (re)loaded
+    pub(crate) fn reset(&mut self, value: &T) {
+        self.saved = Some(value.clone());
+    }
+
+    /// Record the value before a change to `value`, dropping the oldest value when full
+    ///
+    /// Values restored by undo or redo are already tracked, so they aren't recorded again.
+    pub(crate) fn record(&mut self, value: &T) {
+        let Some(previous) = self.saved.replace(value.clone()) else {
+            return;
+        };
+        if previous == *value {
+            return;
+        }
+
+        self.undo.push_back(previous);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    /// Take the value before `current`, moving `current` to the redo stack
+    pub(crate) fn undo(&mut self, current: &T) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current.clone());
+        self.saved = Some(previous.clone());
+        Some(previous)
+    }
+
+    /// Take the last undone value, moving `current` back to the undo stack
+    pub(crate) fn redo(&mut self, current: &T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current.clone());
+        self.saved = Some(next.clone());
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+    struct Volume(i32);
+
+    impl Settings for Volume {
+        fn type_name() -> &'static str {
+            "Volume"
+        }
+    }
+
+    fn history(values: &[i32], capacity: usize) -> SettingsHistory<Volume> {
+        let mut history = SettingsHistory::new(capacity);
+        history.reset(&Volume(0));
+        for value in values {
+            history.record(&Volume(*value));
+        }
+        history
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut history = history(&[1, 2], 8);
+
+        assert_eq!(history.undo(&Volume(2)), Some(Volume(1)));
+        assert_eq!(history.undo(&Volume(1)), Some(Volume(0)));
+        assert_eq!(history.undo(&Volume(0)), None);
+        // Restoring an undone value doesn't record it again
+        history.record(&Volume(0));
+        assert_eq!(history.redo(&Volume(0)), Some(Volume(1)));
+        assert_eq!(history.redo(&Volume(1)), Some(Volume(2)));
+        assert_eq!(history.redo(&Volume(2)), None);
+    }
+
+    #[test]
+    fn test_change_clears_redo() {
+        let mut history = history(&[1, 2], 8);
+
+        assert_eq!(history.undo(&Volume(2)), Some(Volume(1)));
+        history.record(&Volume(1));
+        history.record(&Volume(5));
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(&Volume(5)), Some(Volume(1)));
+    }
+
+    #[test]
+    fn test_capacity_drops_oldest() {
+        let mut history = history(&[1, 2, 3], 2);
+
+        assert_eq!(history.undo(&Volume(3)), Some(Volume(2)));
+        assert_eq!(history.undo(&Volume(2)), Some(Volume(1)));
+        assert_eq!(history.undo(&Volume(1)), None);
+    }
+}
@@ -1,17 +1,58 @@
 pub use bevy_settings_derive::Settings;
 
+#[cfg(feature = "bevy_asset")]
+mod asset;
+mod commands;
+mod encryption;
 mod error;
 mod format;
+mod history;
+mod messages;
+mod migration;
+mod overrides;
 mod plugin;
+mod shared;
+#[cfg(feature = "bevy_state")]
+mod state;
 mod storage;
+mod telemetry;
 mod trait_def;
+mod validation;
+mod watch;
 
+#[cfg(feature = "bevy_asset")]
+pub use asset::{JsonSettingsAsset, JsonSettingsAssetLoader};
+pub use commands::{list_profiles, SettingsCommandsExt};
 pub use error::SettingsError;
-pub use format::SerializationFormat;
-pub use plugin::SettingsPlugin;
+pub use format::{big_int, human_duration};
+pub use format::{
+    platform_default_format, FileEncoding, FloatParseMode, JsonOptions, JsonSortMode,
+    SerializationFormat,
+};
+pub use history::SettingsHistory;
+pub use messages::{SettingsChangeSource, SettingsChanged, SettingsLoadedEvent};
+pub use migration::{
+    CaseConvention, DowngradePolicy, MigrationErrorPolicy, MigrationFn, MigrationHelpers,
+    MigrationLog, MigrationLogEntry, SettingsVersionInfo, VersionInfo, VersionedMigrationFn,
+    IMPLICIT_INITIAL_VERSION,
+};
+pub use plugin::{SettingsLoadPending, SettingsPlugin};
+pub use shared::SharedSettings;
+pub use storage::{flush_settings_now, ExternalEditPolicy, SectionSortOrder, StorageStats};
+pub use telemetry::{
+    CollectingObserver, LoggingTelemetry, NoopObserver, SettingsObservabilityEvent,
+    SettingsObserver, SettingsTelemetry,
+};
 pub use trait_def::Settings;
+pub use validation::SettingsValidationFailed;
+pub use watch::SettingsFileChanged;
+
+pub use semver;
+pub use serde_json;
 
 /// Re-export commonly used types
 pub mod prelude {
-    pub use crate::{SerializationFormat, Settings, SettingsError, SettingsPlugin};
+    pub use crate::{
+        SerializationFormat, Settings, SettingsCommandsExt, SettingsError, SettingsPlugin,
+    };
 }
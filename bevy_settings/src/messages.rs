@@ -0,0 +1,45 @@
+use crate::Settings;
+use bevy::prelude::*;
+
+/// Where the values of a [`SettingsChanged`] or [`SettingsLoadedEvent`] message came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsChangeSource {
+    /// Loaded from the settings file, or the defaults if the file has no values for the type
+    File,
+    /// Loaded with fields overridden by environment variables, see
+    /// [`SettingsPlugin::with_env_prefix`](crate::SettingsPlugin::with_env_prefix)
+    EnvOverride,
+    /// Loaded with fields overridden by command-line arguments, see
+    /// [`SettingsPlugin::with_args`](crate::SettingsPlugin::with_args)
+    ArgOverride,
+    /// Reloaded after the settings file was modified outside of the app, see
+    /// [`SettingsPlugin::with_hot_reload`](crate::SettingsPlugin::with_hot_reload)
+    HotReload,
+    /// Changed by the app, through the resource or a settings command
+    Command,
+}
+
+/// Message sent when changed settings of type `T` were saved
+///
+/// Sent once per successful save, in `PostUpdate`. With a write delay or save debounce, it is
+/// sent once the pending change was written, with the latest value, and not at all if the
+/// write fails.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct SettingsChanged<T: Settings> {
+    /// The saved settings
+    pub value: T,
+    /// What changed the settings
+    pub source: SettingsChangeSource,
+}
+
+/// Message sent when settings of type `T` were loaded
+///
+/// Sent while building the plugin, so it can be read in the first frame. Lazily registered
+/// types and types registered for a state send it when they are loaded.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct SettingsLoadedEvent<T: Settings> {
+    /// The loaded settings, with overrides applied
+    pub value: T,
+    /// Where the settings were loaded from
+    pub source: SettingsChangeSource,
+}
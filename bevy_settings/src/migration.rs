@@ -0,0 +1,759 @@
+use crate::{Settings, SettingsError};
+use bevy::prelude::Resource;
+use semver::{Op, Version, VersionReq};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Version assumed for sections that were saved without a version
+///
+/// Settings saved before their type was registered with a version have no entry in the
+/// `_versions` section. They are older than any real version and are migrated from `0.0.0`.
+pub const IMPLICIT_INITIAL_VERSION: Version = Version::new(0, 0, 0);
+
+/// Function that upgrades a stored settings delta to the current schema version
+///
+/// Called with the version the delta was saved with and the delta itself, which can be
+/// modified in place. Returns whether the delta was changed. Deltas saved without a version are
+/// migrated from [`IMPLICIT_INITIAL_VERSION`].
+///
+/// # Example
+/// ```
+/// # use bevy_settings::{semver::Version, SettingsError, IMPLICIT_INITIAL_VERSION};
+/// # use serde_json::Value;
+/// fn migrate_audio(from: &Version, delta: &mut Value) -> Result<bool, SettingsError> {
+///     let mut changed = false;
+///     if *from == IMPLICIT_INITIAL_VERSION {
+///         // Saved before versioning, the volume was called "vol"
+///         if let Some(volume) = delta.as_object_mut().and_then(|d| d.remove("vol")) {
+///             delta["volume"] = volume;
+///             changed = true;
+///         }
+///     }
+///     if from.major < 2 {
+///         if let Some(volume) = delta.get("volume").and_then(Value::as_f64) {
+///             // Volume used to be stored as a percentage
+///             delta["volume"] = (volume / 100.0).into();
+///             changed = true;
+///         }
+///     }
+///     Ok(changed)
+/// }
+/// ```
+pub type MigrationFn = fn(from: &Version, delta: &mut Value) -> Result<bool, SettingsError>;
+
+/// Function that upgrades a stored settings delta saved with a version in the range it handles
+///
+/// Returned by [`Settings::migrations`] together with that range. Called with the version the
+/// delta currently has, the range and the delta, which can be modified in place. Migrations
+/// later in a chain are called with the lowest version of their range. Returns whether the
+/// delta was changed.
+///
+/// # Example
+/// ```
+/// # use bevy_settings::{semver::{Version, VersionReq}, SettingsError};
+/// # use serde_json::Value;
+/// // Registered for `>=1.0.0, <2.0.0` and `>=2.0.0, <3.0.0`
+/// fn scale_volume(
+///     _from: &Version,
+///     range: &VersionReq,
+///     delta: &mut Value,
+/// ) -> Result<bool, SettingsError> {
+///     let Some(volume) = delta.get("volume").and_then(Value::as_f64) else {
+///         return Ok(false);
+///     };
+///     // Version 1 stored a percentage, version 2 a value from 0 to 10
+///     let scale = if range.matches(&Version::new(1, 0, 0)) { 100.0 } else { 10.0 };
+///     delta["volume"] = (volume / scale).into();
+///     Ok(true)
+/// }
+/// ```
+pub type VersionedMigrationFn =
+    fn(from: &Version, range: &VersionReq, delta: &mut Value) -> Result<bool, SettingsError>;
+
+/// Run the migrations of [`Settings::migrations`] that lead from `from` to `target`
+///
+/// Migrations run in ascending order of the lowest version their range accepts: first the one
+/// whose range matches `from`, then every migration whose range starts between `from` and
+/// `target`, each on the output of the previous one. Chained migrations are called with the
+/// lowest version of their range. Returns `None` if no migration applies.
+pub(crate) fn run_versioned_migrations(
+    mut migrations: Vec<(VersionReq, VersionedMigrationFn)>,
+    from: &Version,
+    target: &Version,
+    delta: &mut Value,
+) -> Option<Result<bool, SettingsError>> {
+    migrations.sort_by_cached_key(|(req, _)| lowest_version(req));
+
+    let mut ran = false;
+    let mut changed = false;
+    for (req, migrate) in &migrations {
+        let lowest = lowest_version(req);
+        let version = if req.matches(from) {
+            from
+        } else if from < &lowest && &lowest < target {
+            &lowest
+        } else {
+            continue;
+        };
+
+        ran = true;
+        match migrate(version, req, delta) {
+            Ok(migrated) => changed |= migrated,
+            Err(e) => return Some(Err(e)),
+        }
+    }
+
+    ran.then_some(Ok(changed))
+}
+
+/// Get the lowest version a requirement accepts, ignoring pre-releases
+///
+/// Requirements without a lower bound, like `<2.0.0`, start at [`IMPLICIT_INITIAL_VERSION`].
+fn lowest_version(req: &VersionReq) -> Version {
+    req.comparators
+        .iter()
+        .filter(|comparator| {
+            matches!(
+                comparator.op,
+                Op::Exact | Op::Greater | Op::GreaterEq | Op::Tilde | Op::Caret | Op::Wildcard
+            )
+        })
+        .map(|comparator| {
+            Version::new(
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            )
+        })
+        .max()
+        .unwrap_or(IMPLICIT_INITIAL_VERSION)
+}
+
+/// What to do when a migration returns an error
+///
+/// With either warning policy the stored version of the section is kept, so the migration is
+/// tried again on the next load.
+///
+/// See [`SettingsPlugin::with_migration_error_policy`](crate::SettingsPlugin::with_migration_error_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationErrorPolicy {
+    /// Log a warning and load the delta as it was before the migration
+    #[default]
+    WarnAndUseDelta,
+    /// Log a warning and discard the delta, falling back to defaults
+    WarnAndUseDefaults,
+    /// Panic, for deployments where loading unmigrated settings is never acceptable
+    Panic,
+}
+
+/// What to do when a settings section was saved by a newer schema version than registered
+///
+/// See [`SettingsPlugin::with_downgrade_policy`](crate::SettingsPlugin::with_downgrade_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DowngradePolicy {
+    /// Log a warning and load the newer delta as far as it fits
+    #[default]
+    Warn,
+    /// Log a warning and fall back to defaults
+    ///
+    /// The newer section and its version are kept in the file as they are, so they are loaded
+    /// again once the newer version runs. Changes made to the settings meanwhile aren't saved.
+    UseDefaults,
+}
+
+/// Record of a migration that changed stored settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationLogEntry {
+    /// Type name of the migrated settings
+    pub type_name: &'static str,
+    /// Version the settings were stored with, `None` if unknown
+    pub from_version: Option<Version>,
+    /// Version the settings were migrated to
+    pub to_version: Version,
+    /// Fields that were added or changed by the migration
+    pub changes: Value,
+}
+
+/// Resource listing the migrations that changed stored settings, for auditing
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MigrationLog {
+    pub entries: Vec<MigrationLogEntry>,
+}
+
+/// Naming convention of field keys, as set by `#[serde(rename_all = "...")]`
+///
+/// See [`MigrationHelpers::convert_case`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseConvention {
+    /// `max_fps`
+    SnakeCase,
+    /// `maxFps`
+    CamelCase,
+    /// `max-fps`
+    KebabCase,
+}
+
+impl CaseConvention {
+    /// Split a key in this convention into its lowercase words
+    fn split(self, key: &str) -> Vec<String> {
+        match self {
+            Self::SnakeCase => key.split('_').map(str::to_lowercase).collect(),
+            Self::KebabCase => key.split('-').map(str::to_lowercase).collect(),
+            Self::CamelCase => {
+                let mut words = vec![String::new()];
+                for c in key.chars() {
+                    if c.is_uppercase() && !words[words.len() - 1].is_empty() {
+                        words.push(String::new());
+                    }
+                    words.last_mut().unwrap().extend(c.to_lowercase());
+                }
+                words
+            }
+        }
+    }
+
+    /// Join lowercase words into a key in this convention
+    fn join(self, words: &[String]) -> String {
+        match self {
+            Self::SnakeCase => words.join("_"),
+            Self::KebabCase => words.join("-"),
+            Self::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                        _ => word.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Version state of a settings section, see [`SettingsVersionInfo`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VersionInfo {
+    /// Version the type was registered with, `None` if it is unversioned
+    pub target_version: Option<Version>,
+    /// Version the section was stored with, `None` if the file had no version for it
+    pub file_version: Option<Version>,
+    /// Whether the stored section was migrated to the target version
+    pub migrated: bool,
+}
+
+/// Resource with the version state of each loaded settings type, by type name
+///
+/// Lets systems tell freshly installed settings apart from migrated ones. Types that are
+/// loaded lazily or on a state transition are added when they are loaded, types registered as
+/// assets are not included.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SettingsVersionInfo {
+    pub sections: HashMap<&'static str, VersionInfo>,
+}
+
+impl SettingsVersionInfo {
+    /// Get the version state of a settings type, if it has been loaded
+    pub fn get<T: Settings>(&self) -> Option<&VersionInfo> {
+        self.sections.get(T::type_name())
+    }
+}
+
+/// Helpers for common changes to a stored delta inside a [`MigrationFn`]
+///
+/// All helpers leave the delta unchanged if the fields they operate on are missing, and return
+/// whether they changed anything, so their results can be combined into the return value of
+/// the migration.
+///
+/// # Example
+/// ```
+/// # use bevy_settings::{semver::Version, MigrationHelpers, SettingsError};
+/// # use serde_json::Value;
+/// fn migrate_video(from: &Version, delta: &mut Value) -> Result<bool, SettingsError> {
+///     let mut changed = false;
+///     if from.major < 2 {
+///         changed |= MigrationHelpers::rename_field(delta, "fps_limit", "frame_limit");
+///     }
+///     Ok(changed)
+/// }
+/// ```
+pub struct MigrationHelpers;
+
+impl MigrationHelpers {
+    /// Rename the top-level field `old` to `new`, returning whether `old` was found
+    pub fn rename_field(data: &mut Value, old: &str, new: &str) -> bool {
+        let Some(object) = data.as_object_mut() else {
+            return false;
+        };
+        match object.remove(old) {
+            Some(value) => {
+                object.insert(new.to_string(), value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rename the field at `path` to `new_name`, keeping it inside the same object
+    ///
+    /// For example `&["graphics", "vsync"]` renames `vsync` inside the `graphics` object.
+    /// Returns whether the field was found.
+    pub fn rename_nested_field(data: &mut Value, path: &[&str], new_name: &str) -> bool {
+        let Some((field, parents)) = path.split_last() else {
+            return false;
+        };
+        let parent = parents
+            .iter()
+            .try_fold(data, |value, key| value.get_mut(*key));
+        match parent {
+            Some(parent) => Self::rename_field(parent, field, new_name),
+            None => false,
+        }
+    }
+
+    /// Move the top-level `fields` into the object at `nested_key`, keeping their names
+    ///
+    /// The nested object is created if needed, but only when at least one of the fields exists.
+    /// Nothing is moved if `nested_key` already holds a value that is not an object.
+    /// Returns whether any field was moved.
+    pub fn move_fields_to_nested(data: &mut Value, fields: &[&str], nested_key: &str) -> bool {
+        let Some(object) = data.as_object_mut() else {
+            return false;
+        };
+        if object
+            .get(nested_key)
+            .is_some_and(|nested| !nested.is_object())
+        {
+            return false;
+        }
+        let moved: Map<String, Value> = fields
+            .iter()
+            .filter_map(|field| {
+                object
+                    .remove(*field)
+                    .map(|value| (field.to_string(), value))
+            })
+            .collect();
+        if moved.is_empty() {
+            return false;
+        }
+        if let Value::Object(nested) = object
+            .entry(nested_key)
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            nested.extend(moved);
+        }
+        true
+    }
+
+    /// Rename several top-level fields at once, given as `(old, new)` pairs
+    ///
+    /// Returns whether any field was renamed.
+    pub fn remap_keys(data: &mut Value, key_map: &[(&str, &str)]) -> bool {
+        key_map.iter().fold(false, |changed, (old, new)| {
+            Self::rename_field(data, old, new) || changed
+        })
+    }
+
+    /// Convert the names of all top-level fields from one naming convention to another
+    ///
+    /// Meant for a changed `#[serde(rename_all = "...")]`. Nested objects keep their keys, since
+    /// nested structs have their own serde attributes. Returns whether any field was renamed.
+    pub fn convert_case(data: &mut Value, from: CaseConvention, to: CaseConvention) -> bool {
+        let Some(object) = data.as_object_mut() else {
+            return false;
+        };
+        let mut changed = false;
+        *object = std::mem::take(object)
+            .into_iter()
+            .map(|(key, value)| {
+                let converted = to.join(&from.split(&key));
+                changed |= converted != key;
+                (converted, value)
+            })
+            .collect();
+        changed
+    }
+
+    /// Append the elements of `additional` to the top-level array `key` that it doesn't contain
+    ///
+    /// Duplicates already in the array are removed as well, and if all elements are strings the
+    /// array is sorted, so merging e.g. default and user `completed_quests` gives a stable
+    /// result. The array is created if missing. Nothing happens if `key` holds a value that is
+    /// not an array. Returns whether the array changed.
+    pub fn merge_arrays_dedup(data: &mut Value, key: &str, additional: Vec<Value>) -> bool {
+        let Some(object) = data.as_object_mut() else {
+            return false;
+        };
+        let Value::Array(array) = object
+            .entry(key)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        else {
+            return false;
+        };
+
+        let original = array.clone();
+        let mut merged: Vec<Value> = Vec::with_capacity(array.len() + additional.len());
+        for value in array.drain(..).chain(additional) {
+            if !merged.contains(&value) {
+                merged.push(value);
+            }
+        }
+        if merged.iter().all(Value::is_string) {
+            merged.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+        }
+        *array = merged;
+        *array != original
+    }
+
+    /// Remove the top-level field `key`, returning whether it was present
+    ///
+    /// Unknown fields are ignored when deserializing, but stay in the stored delta until removed.
+    pub fn remove_field(data: &mut Value, key: &str) -> bool {
+        data.as_object_mut()
+            .is_some_and(|object| object.remove(key).is_some())
+    }
+
+    /// Replace the top-level field `key` with the result of `f`, returning whether it was present
+    ///
+    /// Useful when the meaning of a value changes, e.g. a volume from `0..=100` to `0.0..=1.0`.
+    pub fn transform_field(data: &mut Value, key: &str, f: impl FnOnce(Value) -> Value) -> bool {
+        match data.get_mut(key) {
+            Some(value) => {
+                *value = f(value.take());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rename_field() {
+        let mut data = json!({ "fps_limit": 60, "vsync": true });
+        assert!(MigrationHelpers::rename_field(
+            &mut data,
+            "fps_limit",
+            "frame_limit"
+        ));
+        assert_eq!(data, json!({ "frame_limit": 60, "vsync": true }));
+    }
+
+    #[test]
+    fn test_rename_missing_field_is_noop() {
+        let mut data = json!({ "vsync": true });
+        assert!(!MigrationHelpers::rename_field(
+            &mut data,
+            "fps_limit",
+            "frame_limit"
+        ));
+        assert_eq!(data, json!({ "vsync": true }));
+
+        let mut data = json!(42);
+        assert!(!MigrationHelpers::rename_field(
+            &mut data,
+            "fps_limit",
+            "frame_limit"
+        ));
+        assert!(!MigrationHelpers::rename_nested_field(
+            &mut data,
+            &["a", "b"],
+            "c"
+        ));
+    }
+
+    #[test]
+    fn test_rename_nested_field() {
+        let mut data = json!({ "graphics": { "display": { "vsync": true } }, "vsync": 1 });
+        assert!(MigrationHelpers::rename_nested_field(
+            &mut data,
+            &["graphics", "display", "vsync"],
+            "vertical_sync"
+        ));
+        assert_eq!(
+            data,
+            json!({ "graphics": { "display": { "vertical_sync": true } }, "vsync": 1 })
+        );
+
+        assert!(!MigrationHelpers::rename_nested_field(
+            &mut data,
+            &["audio", "volume"],
+            "master"
+        ));
+        assert!(!MigrationHelpers::rename_nested_field(
+            &mut data,
+            &[],
+            "master"
+        ));
+    }
+
+    #[test]
+    fn test_move_fields_to_nested() {
+        let mut data = json!({ "color_r": 1, "color_g": 2, "color_b": 3, "alpha": 4 });
+        assert!(MigrationHelpers::move_fields_to_nested(
+            &mut data,
+            &["color_r", "color_g", "color_b"],
+            "color"
+        ));
+        assert_eq!(
+            data,
+            json!({ "alpha": 4, "color": { "color_r": 1, "color_g": 2, "color_b": 3 } })
+        );
+    }
+
+    #[test]
+    fn test_move_missing_fields_creates_nothing() {
+        let mut data = json!({ "alpha": 4 });
+        assert!(!MigrationHelpers::move_fields_to_nested(
+            &mut data,
+            &["color_r", "color_g"],
+            "color"
+        ));
+        assert_eq!(data, json!({ "alpha": 4 }));
+
+        let mut data = json!({ "color_r": 1, "color": 5 });
+        assert!(!MigrationHelpers::move_fields_to_nested(
+            &mut data,
+            &["color_r"],
+            "color"
+        ));
+        assert_eq!(data, json!({ "color_r": 1, "color": 5 }));
+    }
+
+    #[test]
+    fn test_remap_keys() {
+        let mut data = json!({ "fpsLimit": 60, "vSync": true, "quality": 2 });
+        assert!(MigrationHelpers::remap_keys(
+            &mut data,
+            &[
+                ("fpsLimit", "frame_limit"),
+                ("vSync", "vsync"),
+                ("missing", "other")
+            ]
+        ));
+        assert_eq!(
+            data,
+            json!({ "quality": 2, "frame_limit": 60, "vsync": true })
+        );
+
+        assert!(!MigrationHelpers::remap_keys(
+            &mut data,
+            &[("fpsLimit", "frame_limit")]
+        ));
+    }
+
+    #[test]
+    fn test_convert_case() {
+        let mut data = json!({ "maxFps": 60, "vsync": true, "uiScale": { "hudSize": 1 } });
+        assert!(MigrationHelpers::convert_case(
+            &mut data,
+            CaseConvention::CamelCase,
+            CaseConvention::SnakeCase
+        ));
+        assert_eq!(
+            data,
+            json!({ "max_fps": 60, "vsync": true, "ui_scale": { "hudSize": 1 } })
+        );
+
+        assert!(MigrationHelpers::convert_case(
+            &mut data,
+            CaseConvention::SnakeCase,
+            CaseConvention::KebabCase
+        ));
+        assert_eq!(
+            data,
+            json!({ "max-fps": 60, "vsync": true, "ui-scale": { "hudSize": 1 } })
+        );
+
+        assert!(MigrationHelpers::convert_case(
+            &mut data,
+            CaseConvention::KebabCase,
+            CaseConvention::CamelCase
+        ));
+        assert_eq!(
+            data,
+            json!({ "maxFps": 60, "vsync": true, "uiScale": { "hudSize": 1 } })
+        );
+
+        assert!(!MigrationHelpers::convert_case(
+            &mut json!({ "vsync": true }),
+            CaseConvention::CamelCase,
+            CaseConvention::SnakeCase
+        ));
+    }
+
+    #[test]
+    fn test_merge_arrays_dedup() {
+        let mut data = json!({ "completed_quests": ["tutorial", "forest", "tutorial"] });
+        assert!(MigrationHelpers::merge_arrays_dedup(
+            &mut data,
+            "completed_quests",
+            vec![json!("cave"), json!("forest")]
+        ));
+        assert_eq!(
+            data,
+            json!({ "completed_quests": ["cave", "forest", "tutorial"] })
+        );
+
+        assert!(!MigrationHelpers::merge_arrays_dedup(
+            &mut data,
+            "completed_quests",
+            vec![json!("cave")]
+        ));
+    }
+
+    #[test]
+    fn test_merge_arrays_dedup_keeps_order_of_other_values() {
+        let mut data = json!({ "muted_channels": [3, 1] });
+        assert!(MigrationHelpers::merge_arrays_dedup(
+            &mut data,
+            "muted_channels",
+            vec![json!(1), json!(2)]
+        ));
+        assert_eq!(data, json!({ "muted_channels": [3, 1, 2] }));
+
+        let mut data = json!({ "volume": 0.5 });
+        assert!(MigrationHelpers::merge_arrays_dedup(
+            &mut data,
+            "muted_channels",
+            vec![json!(4), json!(4)]
+        ));
+        assert_eq!(data, json!({ "volume": 0.5, "muted_channels": [4] }));
+
+        assert!(!MigrationHelpers::merge_arrays_dedup(
+            &mut data,
+            "volume",
+            vec![json!(1)]
+        ));
+    }
+
+    #[test]
+    fn test_remove_field() {
+        let mut data = json!({ "legacy_mode": true, "vsync": true });
+        assert!(MigrationHelpers::remove_field(&mut data, "legacy_mode"));
+        assert_eq!(data, json!({ "vsync": true }));
+
+        assert!(!MigrationHelpers::remove_field(&mut data, "legacy_mode"));
+        assert!(!MigrationHelpers::remove_field(
+            &mut json!(null),
+            "legacy_mode"
+        ));
+        assert_eq!(data, json!({ "vsync": true }));
+    }
+
+    #[test]
+    fn test_transform_field() {
+        let to_fraction = |value: Value| json!(value.as_f64().unwrap_or_default() / 100.0);
+
+        let mut data = json!({ "volume": 80, "muted": false });
+        assert!(MigrationHelpers::transform_field(
+            &mut data,
+            "volume",
+            to_fraction
+        ));
+        assert_eq!(data, json!({ "volume": 0.8, "muted": false }));
+
+        assert!(!MigrationHelpers::transform_field(
+            &mut data,
+            "master",
+            to_fraction
+        ));
+        assert_eq!(data, json!({ "volume": 0.8, "muted": false }));
+    }
+
+    /// Record the version each migration was called with in the `steps` array
+    fn record_step(
+        from: &Version,
+        range: &VersionReq,
+        delta: &mut Value,
+    ) -> Result<bool, SettingsError> {
+        assert!(range.matches(from), "{} doesn't match {}", from, range);
+        delta["steps"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!(from.to_string()));
+        Ok(true)
+    }
+
+    fn fail(
+        _from: &Version,
+        _range: &VersionReq,
+        _delta: &mut Value,
+    ) -> Result<bool, SettingsError> {
+        Err(SettingsError::Migration("failed".to_string()))
+    }
+
+    fn migrations(reqs: &[&str]) -> Vec<(VersionReq, VersionedMigrationFn)> {
+        reqs.iter()
+            .map(|req| {
+                (
+                    VersionReq::parse(req).unwrap(),
+                    record_step as VersionedMigrationFn,
+                )
+            })
+            .collect()
+    }
+
+    fn run(reqs: &[&str], from: &str, target: &str) -> Option<Vec<Value>> {
+        let mut delta = json!({ "steps": [] });
+        let result = run_versioned_migrations(
+            migrations(reqs),
+            &Version::parse(from).unwrap(),
+            &Version::parse(target).unwrap(),
+            &mut delta,
+        )?;
+        assert!(result.unwrap());
+        Some(delta["steps"].as_array().unwrap().clone())
+    }
+
+    #[test]
+    fn test_versioned_migrations_chain_in_order() {
+        let reqs = [">=3.0.0, <4.0.0", ">=1.0.0, <2.0.0", ">=2.0.0, <3.0.0"];
+
+        assert_eq!(
+            run(&reqs, "1.2.0", "4.0.0").unwrap(),
+            vec![json!("1.2.0"), json!("2.0.0"), json!("3.0.0")]
+        );
+        assert_eq!(
+            run(&reqs, "2.5.0", "4.0.0").unwrap(),
+            vec![json!("2.5.0"), json!("3.0.0")]
+        );
+        // Migrations for versions at or after the target don't run
+        assert_eq!(run(&reqs, "1.0.0", "3.0.0").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_versioned_migrations_without_match() {
+        assert_eq!(run(&[">=1.0.0, <2.0.0"], "2.0.0", "3.0.0"), None);
+        assert_eq!(run(&[], "1.0.0", "3.0.0"), None);
+        // Sections saved without a version
+        assert_eq!(
+            run(&["<1.0.0"], "0.0.0", "1.0.0").unwrap(),
+            vec![json!("0.0.0")]
+        );
+    }
+
+    #[test]
+    fn test_versioned_migration_error_stops_chain() {
+        let migrations: Vec<(VersionReq, VersionedMigrationFn)> = vec![
+            (VersionReq::parse("^1").unwrap(), fail),
+            (VersionReq::parse("^2").unwrap(), record_step),
+        ];
+        let mut delta = json!({ "steps": [] });
+
+        let result = run_versioned_migrations(
+            migrations,
+            &Version::new(1, 0, 0),
+            &Version::new(3, 0, 0),
+            &mut delta,
+        );
+        assert!(matches!(result, Some(Err(SettingsError::Migration(_)))));
+        assert_eq!(delta, json!({ "steps": [] }));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::storage::{merge_values, MergeOptions};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A field value set outside the settings file, applied on load but never saved
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FieldOverride {
+    /// Keys leading to the field, starting at the section
+    pub path: Vec<String>,
+    /// Value replacing the loaded one
+    pub value: Value,
+}
+
+/// Parse an override value as a JSON scalar, falling back to a string
+///
+/// `0.5`, `true` and `"quoted"` keep their JSON types, anything that isn't valid JSON like
+/// `fullscreen` becomes a string.
+pub(crate) fn parse_override_value(raw: &str) -> Value {
+    match serde_json::from_str(raw) {
+        Ok(value @ (Value::Bool(_) | Value::Number(_) | Value::String(_) | Value::Null)) => value,
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+/// Collect the overrides from environment variables named `{PREFIX}_{SECTION}_{FIELD}`
+///
+/// The section is the section name in upper case, with or without a trailing `SETTINGS`, so
+/// both `MYGAME_AUDIOSETTINGS_MASTER` and `MYGAME_AUDIO_MASTER` set `master` in the
+/// `audiosettings` section. Nested fields are separated by a double underscore, as in
+/// `MYGAME_GRAPHICS_RESOLUTION__WIDTH`. Field names are lower case.
+pub(crate) fn env_overrides(
+    prefix: &str,
+    sections: &[String],
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> HashMap<String, Vec<FieldOverride>> {
+    let prefix = format!("{}_", prefix.to_lowercase());
+    let mut overrides: HashMap<String, Vec<FieldOverride>> = HashMap::new();
+
+    for (name, raw) in vars {
+        let Some(rest) = name
+            .to_lowercase()
+            .strip_prefix(&prefix)
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let matched = sections.iter().find_map(|section| {
+            let field = section_names(&section.replace('-', "_")).find_map(|name| {
+                Some(
+                    rest.strip_prefix(name.as_str())?
+                        .strip_prefix('_')?
+                        .to_string(),
+                )
+            })?;
+            Some((section, field.split("__").map(str::to_string).collect()))
+        });
+        if let Some((section, path)) = matched {
+            push_override(&mut overrides, section, path, &raw);
+        }
+    }
+
+    overrides
+}
+
+/// Collect the overrides from command-line arguments like `--setting.{section}.{field}=value`
+///
+/// The section is matched like in [`env_overrides`], ignoring case and a trailing `settings`.
+/// Nested fields are separated by dots, as in `--setting.graphics.resolution.width=1920`.
+/// Other arguments are ignored.
+pub(crate) fn arg_overrides(
+    sections: &[String],
+    args: impl IntoIterator<Item = String>,
+) -> HashMap<String, Vec<FieldOverride>> {
+    let mut overrides: HashMap<String, Vec<FieldOverride>> = HashMap::new();
+
+    for arg in args {
+        let Some((name, raw)) = arg
+            .strip_prefix("--setting.")
+            .and_then(|arg| arg.split_once('='))
+        else {
+            continue;
+        };
+        let Some((section_name, field)) = name.split_once('.') else {
+            continue;
+        };
+        let section_name = section_name.to_lowercase();
+        let section = sections
+            .iter()
+            .find(|section| section_names(section).any(|name| name == section_name));
+        if let Some(section) = section {
+            let path = field.split('.').map(str::to_string).collect();
+            push_override(&mut overrides, section, path, raw);
+        }
+    }
+
+    overrides
+}
+
+/// Names a section can be referred to by, its name and the name without a trailing `settings`
+fn section_names(section: &str) -> impl Iterator<Item = String> {
+    let section = section.to_lowercase();
+    let short = section
+        .strip_suffix("settings")
+        .filter(|short| !short.is_empty())
+        .map(str::to_string);
+    std::iter::once(section).chain(short)
+}
+
+/// Add an override, skipping paths with empty keys
+fn push_override(
+    overrides: &mut HashMap<String, Vec<FieldOverride>>,
+    section: &str,
+    path: Vec<String>,
+    raw: &str,
+) {
+    if path.iter().any(String::is_empty) {
+        return;
+    }
+    overrides
+        .entry(section.to_string())
+        .or_default()
+        .push(FieldOverride {
+            path,
+            value: parse_override_value(raw),
+        });
+}
+
+/// Merge overrides into the serialized settings of a section
+pub(crate) fn apply_overrides(value: &mut Value, overrides: &[FieldOverride]) {
+    for field in overrides {
+        let patch = field
+            .path
+            .iter()
+            .rev()
+            .fold(field.value.clone(), |patch, key| {
+                Value::Object(Map::from_iter([(key.clone(), patch)]))
+            });
+        merge_values(value, &patch, MergeOptions::default());
+    }
+}
+
+/// Restore the persisted values of overridden fields that still hold their override
+///
+/// Fields that were changed since are kept, so changes made at runtime are saved as usual.
+pub(crate) fn revert_overrides(value: &mut Value, persisted: &Value, overrides: &[FieldOverride]) {
+    for field in overrides {
+        let Some((key, parents)) = field.path.split_last() else {
+            continue;
+        };
+        let Some(Value::Object(parent)) = parents
+            .iter()
+            .try_fold(&mut *value, |value, key| value.get_mut(key))
+        else {
+            continue;
+        };
+        if parent.get(key) != Some(&field.value) {
+            continue;
+        }
+
+        let original = field
+            .path
+            .iter()
+            .try_fold(persisted, |value, key| value.get(key));
+        match original {
+            Some(original) => {
+                parent.insert(key.clone(), original.clone());
+            }
+            None => {
+                parent.remove(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_override_value() {
+        assert_eq!(parse_override_value("0.5"), json!(0.5));
+        assert_eq!(parse_override_value("1920"), json!(1920));
+        assert_eq!(parse_override_value("true"), json!(true));
+        assert_eq!(parse_override_value("\"quoted\""), json!("quoted"));
+        assert_eq!(parse_override_value("fullscreen"), json!("fullscreen"));
+        assert_eq!(parse_override_value("[1, 2]"), json!("[1, 2]"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let sections = vec!["audiosettings".to_string(), "graphics".to_string()];
+        let overrides = env_overrides(
+            "mygame",
+            &sections,
+            vars(&[
+                ("MYGAME_AUDIO_MASTER_VOLUME", "0.5"),
+                ("MYGAME_AUDIOSETTINGS_MUTED", "true"),
+                ("MYGAME_GRAPHICS_RESOLUTION__WIDTH", "1920"),
+                ("MYGAME_NETWORK_PORT", "80"),
+                ("OTHER_AUDIO_MASTER_VOLUME", "1.0"),
+                ("MYGAME_GRAPHICS_", "1"),
+            ]),
+        );
+
+        assert_eq!(
+            overrides["audiosettings"],
+            vec![
+                FieldOverride {
+                    path: vec!["master_volume".to_string()],
+                    value: json!(0.5),
+                },
+                FieldOverride {
+                    path: vec!["muted".to_string()],
+                    value: json!(true),
+                },
+            ]
+        );
+        assert_eq!(
+            overrides["graphics"],
+            vec![FieldOverride {
+                path: vec!["resolution".to_string(), "width".to_string()],
+                value: json!(1920),
+            }]
+        );
+        assert_eq!(overrides.len(), 2);
+    }
+
+    #[test]
+    fn test_arg_overrides() {
+        let sections = vec!["testsettings".to_string(), "graphics".to_string()];
+        let args = [
+            "game",
+            "--setting.test.value=42",
+            "--setting.TestSettings.scale=1.5",
+            "--setting.test.enabled=false",
+            "--setting.test.name=Player One",
+            "--setting.graphics.resolution.width=1920",
+            "--setting.network.port=80",
+            "--setting.test.missing_value",
+            "--fullscreen",
+        ];
+        let overrides = arg_overrides(&sections, args.map(str::to_string));
+
+        let values: Vec<(&str, &Value)> = overrides["testsettings"]
+            .iter()
+            .map(|field| (field.path[0].as_str(), &field.value))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("value", &json!(42)),
+                ("scale", &json!(1.5)),
+                ("enabled", &json!(false)),
+                ("name", &json!("Player One")),
+            ]
+        );
+        assert_eq!(
+            overrides["graphics"],
+            vec![FieldOverride {
+                path: vec!["resolution".to_string(), "width".to_string()],
+                value: json!(1920),
+            }]
+        );
+        assert_eq!(overrides.len(), 2);
+    }
+
+    #[test]
+    fn test_apply_and_revert_overrides() {
+        let overrides = vec![
+            FieldOverride {
+                path: vec!["volume".to_string()],
+                value: json!(0.5),
+            },
+            FieldOverride {
+                path: vec!["resolution".to_string(), "width".to_string()],
+                value: json!(1920),
+            },
+        ];
+        let persisted = json!({ "volume": 0.8, "resolution": { "width": 800, "height": 600 } });
+
+        let mut value = persisted.clone();
+        apply_overrides(&mut value, &overrides);
+        assert_eq!(
+            value,
+            json!({ "volume": 0.5, "resolution": { "width": 1920, "height": 600 } })
+        );
+
+        // Changed at runtime, so the new volume is kept
+        value["volume"] = json!(0.3);
+        revert_overrides(&mut value, &persisted, &overrides);
+        assert_eq!(
+            value,
+            json!({ "volume": 0.3, "resolution": { "width": 800, "height": 600 } })
+        );
+    }
+}
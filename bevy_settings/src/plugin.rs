@@ -1,13 +1,32 @@
 use crate::{
+    encryption::derive_key,
+    history::SettingsHistory,
+    messages::{SettingsChangeSource, SettingsChanged, SettingsLoadedEvent},
+    migration::{
+        run_versioned_migrations, DowngradePolicy, MigrationErrorPolicy, MigrationFn, MigrationLog,
+        MigrationLogEntry, SettingsVersionInfo, VersionInfo, IMPLICIT_INITIAL_VERSION,
+    },
+    overrides::{arg_overrides, env_overrides},
+    platform_default_format,
     storage::{
-        get_type_key, merge_with_defaults, save_settings_on_change, SettingsManager, Storage,
+        apply_serde_aliases, check_schema, compute_value_delta, flush_pending_save, get_type_key,
+        is_valid_type_key, json_size, merge_with_base, redact, save_settings_on_change,
+        validate_delta, ExternalEditPolicy, PendingSave, SectionSortOrder, SettingsManager,
+        SettingsTypeConfig, Storage, StorageStats, VersionCheck, VERSIONS_KEY,
+    },
+    telemetry::{SettingsObservabilityEvent, SettingsObserver, SettingsTelemetry},
+    validation::{SettingsValidationFailed, SettingsValidators, ValidatorRegistration},
+    watch::{
+        poll_settings_file, receive_hot_reload, HotReload, SettingsFileChanged, SettingsFileWatch,
     },
-    SerializationFormat, Settings,
+    FileEncoding, JsonOptions, JsonSortMode, SerializationFormat, Settings,
 };
 use bevy::prelude::*;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
 use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Plugin for managing all settings in Bevy using a fluent builder API with storage.
 ///
@@ -39,6 +58,23 @@ use std::sync::{Arc, Mutex};
 pub struct SettingsPlugin {
     storage: Storage,
     handlers: Vec<Box<dyn SettingsHandler>>,
+    migration_error_policy: MigrationErrorPolicy,
+    downgrade_policy: DowngradePolicy,
+    initial_capacity: Option<usize>,
+    write_delay: Option<Duration>,
+    save_debounce: Option<Duration>,
+    history_depth: Option<usize>,
+    env_prefix: Option<String>,
+    env_vars: Option<Vec<(String, String)>>,
+    args: Vec<String>,
+    schema_validation: bool,
+    migration_dry_run: bool,
+    watch_interval: Option<Duration>,
+    hot_reload: bool,
+    file_watcher_debounce: Duration,
+    schema_output: Option<PathBuf>,
+    /// Insert the registered validators, one entry per validator
+    validators: Vec<ValidatorRegistration>,
 }
 
 impl SettingsPlugin {
@@ -47,7 +83,61 @@ impl SettingsPlugin {
         Self {
             storage,
             handlers: Vec::new(),
+            migration_error_policy: MigrationErrorPolicy::default(),
+            downgrade_policy: DowngradePolicy::default(),
+            initial_capacity: None,
+            write_delay: None,
+            save_debounce: None,
+            history_depth: None,
+            env_prefix: None,
+            env_vars: None,
+            args: Vec::new(),
+            schema_validation: false,
+            migration_dry_run: false,
+            watch_interval: None,
+            hot_reload: false,
+            file_watcher_debounce: Duration::ZERO,
+            schema_output: None,
+            validators: Vec::new(),
+        }
+    }
+
+    /// Create the plugin from environment variables, for containerized deployments.
+    ///
+    /// - `BEVY_SETTINGS_NAME` - file name, defaults to `Settings`
+    /// - `BEVY_SETTINGS_FORMAT` - `json`, `yaml` (with the `yaml` feature), `toml` or `binary`,
+    ///   defaults to `json`
+    /// - `BEVY_SETTINGS_PATH` - base path, defaults to `settings`
+    ///
+    /// Unset or unrecognized values fall back to the defaults.
+    pub fn new_from_env() -> Self {
+        Self::new_from_env_with(|name| std::env::var(name).ok())
+    }
+
+    /// Like [`new_from_env`](Self::new_from_env), reading the variables through `lookup`.
+    pub fn new_from_env_with(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut plugin =
+            Self::new(lookup("BEVY_SETTINGS_NAME").unwrap_or_else(|| "Settings".to_string()));
+
+        if let Some(format) = lookup("BEVY_SETTINGS_FORMAT") {
+            match format.to_lowercase().as_str() {
+                "json" => plugin.storage.format = SerializationFormat::Json,
+                "binary" => plugin.storage.format = SerializationFormat::Binary,
+                #[cfg(feature = "yaml")]
+                "yaml" => plugin.storage.format = SerializationFormat::Yaml,
+                "toml" => plugin.storage.format = SerializationFormat::Toml,
+                _ => warn!(
+                    "Unknown settings format {} in BEVY_SETTINGS_FORMAT, using JSON",
+                    format
+                ),
+            }
         }
+
+        if let Some(path) = lookup("BEVY_SETTINGS_PATH") {
+            plugin = plugin.with_base_path(path);
+        }
+
+        plugin
     }
 
     pub fn format(mut self, format: SerializationFormat) -> Self {
@@ -55,6 +145,12 @@ impl SettingsPlugin {
         self
     }
 
+    /// Use the recommended format for the target platform, see [`platform_default_format`].
+    pub fn with_platform_format(mut self) -> Self {
+        self.storage.format = platform_default_format();
+        self
+    }
+
     pub fn version(mut self, version: impl Into<String>) -> Self {
         self.storage = self.storage.with_version(version);
         self
@@ -65,11 +161,612 @@ impl SettingsPlugin {
         self
     }
 
+    /// Fall back to defaults if the settings file was written by a newer version.
+    ///
+    /// The file version is compared against [`SettingsPlugin::version`] using semver.
+    pub fn with_strict_version_check(mut self) -> Self {
+        self.storage.version_check = VersionCheck::Strict;
+        self
+    }
+
+    /// Log a warning if the settings file was written by a newer version, but load it anyway.
+    pub fn with_lenient_version_check(mut self) -> Self {
+        self.storage.version_check = VersionCheck::Lenient;
+        self
+    }
+
+    /// Allow hand-written comment lines starting with `//` or `#` in JSON settings files.
+    ///
+    /// Comment lines are removed before parsing. Comments after a value on the same line are not
+    /// supported. Comments are not preserved when the file is saved.
+    pub fn with_json_comments(mut self, strip: bool) -> Self {
+        self.storage.strip_json_comments = strip;
+        self
+    }
+
+    /// Choose the text encoding of JSON and YAML settings files.
+    ///
+    /// Defaults to [`FileEncoding::Utf8`]. When loading, a byte order mark in the file takes
+    /// precedence over the configured encoding.
+    pub fn with_file_encoding(mut self, encoding: FileEncoding) -> Self {
+        self.storage.encoding = encoding;
+        self
+    }
+
+    /// Patch the raw contents of the settings file before they are deserialized.
+    ///
+    /// The hook receives the top-level map of the file, including the `version` and `_versions`
+    /// entries, and runs before any section is processed. Useful for migrations that are easier
+    /// to express as JSON transforms, such as renaming a section.
+    pub fn with_pre_load_hook(
+        mut self,
+        hook: impl Fn(&mut Map<String, Value>) + Send + Sync + 'static,
+    ) -> Self {
+        self.storage.pre_load_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `callback` with the path of the settings file whenever it is newly created.
+    ///
+    /// Runs right after the first write of the file, but not when an existing file is updated.
+    /// Useful for setting OS-level attributes such as permissions or ACLs.
+    pub fn with_file_creation_callback(
+        mut self,
+        callback: impl Fn(&Path) + Send + Sync + 'static,
+    ) -> Self {
+        self.storage.file_created_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Report structured events about loading, migrating, validating and saving each section
+    /// to `observer`.
+    pub fn with_observability(
+        mut self,
+        observer: impl SettingsObserver + Send + Sync + 'static,
+    ) -> Self {
+        self.storage.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Report the timings of every read and write of the settings file to `reporter`.
+    ///
+    /// Use [`LoggingTelemetry`](crate::LoggingTelemetry) to log them at debug level.
+    pub fn with_telemetry(
+        mut self,
+        reporter: impl SettingsTelemetry + Send + Sync + 'static,
+    ) -> Self {
+        self.storage.telemetry = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Run migrations and log their changes without writing anything to disk.
+    ///
+    /// Settings are loaded and migrated as usual, but the settings file is never written, not
+    /// even when settings change at runtime. Meant for developing migrations against a real
+    /// settings file.
+    pub fn with_migration_dry_run(mut self) -> Self {
+        self.migration_dry_run = true;
+        self
+    }
+
+    /// Choose what happens when a migration registered with
+    /// [`SettingsPlugin::register_with_version`] returns an error.
+    ///
+    /// Defaults to [`MigrationErrorPolicy::WarnAndUseDelta`].
+    pub fn with_migration_error_policy(mut self, policy: MigrationErrorPolicy) -> Self {
+        self.migration_error_policy = policy;
+        self
+    }
+
+    /// Choose what happens when a settings section was saved by a newer schema version than
+    /// the one registered with [`SettingsPlugin::register_with_version`], i.e. after the app
+    /// was downgraded.
+    ///
+    /// Defaults to [`DowngradePolicy::Warn`]. Either way the newer version stays recorded in the
+    /// file, so upgrading again doesn't re-run migrations on newer data.
+    pub fn with_downgrade_policy(mut self, policy: DowngradePolicy) -> Self {
+        self.downgrade_policy = policy;
+        self
+    }
+
+    /// Merge arrays in loaded settings element by element instead of replacing them.
+    ///
+    /// Objects at the same index are merged recursively, so a hand-edited file can change a
+    /// single field of one element, e.g. `"keybinds": [{ "key": "W" }, {}]` only changes the key
+    /// of the first binding. The array still takes the length of the stored array. Defaults to
+    /// `false`, which replaces arrays wholesale.
+    pub fn with_merge_arrays_by_index(mut self, enabled: bool) -> Self {
+        self.storage.merge_options.arrays_by_index = enabled;
+        self
+    }
+
+    /// Configure how floats are decoded when loading settings.
+    ///
+    /// By default numbers that overflow an `f32` field are rejected, so a broken value in the file
+    /// falls back to the defaults instead of silently producing an unusable resource.
+    pub fn with_serde_options(mut self, options: JsonOptions) -> Self {
+        self.storage.merge_options.json = options;
+        self
+    }
+
+    /// Check the settings file for modifications every `interval`.
+    ///
+    /// Polls the modification time of the file, so it also works on platforms without native
+    /// file watching. Changes made outside of the app are reported with a
+    /// [`SettingsFileChanged`] message, writes of the app itself are ignored. Not supported on
+    /// WebAssembly, where no messages are sent.
+    pub fn with_watch_interval(mut self, interval: Duration) -> Self {
+        self.watch_interval = Some(interval);
+        self
+    }
+
+    /// Encrypt the settings file with AES-256-GCM using `key`.
+    ///
+    /// Every write uses a new random nonce, stored in front of the ciphertext. The file gets an
+    /// additional `.enc` extension, e.g. `GameSettings.json.enc`. A file that can't be
+    /// decrypted, because of a wrong key or because it was modified, fails to load with
+    /// [`SettingsError::DecryptionFailed`](crate::SettingsError::DecryptionFailed) and is left
+    /// in place for a retry with the right key. Saves are skipped until it was loaded, so it
+    /// isn't replaced with defaults. A file too short to be encrypted is moved to
+    /// `<file>.corrupt`.
+    pub fn with_encryption(mut self, key: [u8; 32]) -> Self {
+        self.storage.encryption_key = Some(key);
+        self
+    }
+
+    /// Encrypt the settings file with a key derived from `passphrase`.
+    ///
+    /// The key is derived with PBKDF2-HMAC-SHA256, salted with the settings name, which takes
+    /// a noticeable moment, especially in debug builds. See
+    /// [`with_encryption`](Self::with_encryption).
+    pub fn with_passphrase(self, passphrase: &str) -> Self {
+        let key = derive_key(passphrase, &self.storage.filename);
+        self.with_encryption(key)
+    }
+
+    /// Reload the settings when the settings file is modified outside of the app.
+    ///
+    /// A background thread watches the file and the settings are reloaded in `PostUpdate`,
+    /// with migrations, validation and overrides applied like on startup. Reloaded values
+    /// are reported by change detection as usual, unchanged values are left untouched. Lazy
+    /// types are reloaded once they are loaded, types registered for a state or as an asset
+    /// aren't reloaded. Not supported on WebAssembly.
+    pub fn with_hot_reload(mut self) -> Self {
+        self.hot_reload = true;
+        self
+    }
+
+    /// Report modifications of the settings file at most once per `debounce`.
+    ///
+    /// Editors often write a file in several steps. The first modification is reported right
+    /// away, later ones inside the window are held back and reported together once it has
+    /// passed. Applies to [`with_hot_reload`](Self::with_hot_reload) and
+    /// [`with_watch_interval`](Self::with_watch_interval). Defaults to no debounce.
+    pub fn with_file_watcher_debounce(mut self, debounce: Duration) -> Self {
+        self.file_watcher_debounce = debounce;
+        self
+    }
+
+    /// Write the JSON Schema of all registered settings to `path` when the plugin is built.
+    ///
+    /// The file describes the settings file, with one property per section holding the schema
+    /// returned by [`Settings::schema`], so editors can offer completion and validation. Types
+    /// without a schema accept any value. Parent directories are created as needed.
+    pub fn with_schema_file_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.schema_output = Some(path.into());
+        self
+    }
+
+    /// Gzip JSON settings files that would be larger than `bytes`.
+    ///
+    /// Smaller files stay plain JSON so they remain easy to edit. Compressed files are detected
+    /// on load and decompressed transparently.
+    pub fn with_compression_threshold(mut self, bytes: usize) -> Self {
+        self.storage.compression_threshold = Some(bytes);
+        self
+    }
+
+    /// Write the temporary file used for atomic saves to `path` instead of the settings
+    /// directory.
+    ///
+    /// Saves first write a temporary file and then move it over the settings file. Use this when
+    /// the settings directory can't hold extra files. If `path` is on another file system, the
+    /// file is copied instead of moved, which is no longer atomic.
+    pub fn with_temp_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.storage.temp_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Guard writes with a `<name>.lock` file next to the settings file.
+    ///
+    /// Protects against corruption when two instances of the app run at the same time. While
+    /// another instance holds the lock, saves are skipped with a warning. A lock left behind
+    /// by a crashed instance is taken over once its process has exited, or after 30 seconds
+    /// where that can't be checked.
+    pub fn with_lock_file(mut self, enabled: bool) -> Self {
+        self.storage.lock_file = enabled;
+        self
+    }
+
+    /// Choose the order of the sections in the written file.
+    ///
+    /// Defaults to [`SectionSortOrder::RegistrationOrder`].
+    pub fn with_section_sort(mut self, order: SectionSortOrder) -> Self {
+        self.storage.section_sort = order;
+        self
+    }
+
+    /// Annotate a section of the settings file with a human-readable comment.
+    ///
+    /// JSON has no comments, so the comment is written as a `"_comment_<section>"` pseudo-key
+    /// right before the section, in YAML files as well. Comments are ignored on load and not
+    /// written to binary files.
+    pub fn with_section_comment(mut self, section: &str, comment: &str) -> Self {
+        self.storage
+            .section_comments
+            .insert(section.to_string(), comment.to_string());
+        self
+    }
+
+    /// Detect sections of the settings file that were edited outside of the app.
+    ///
+    /// A `_content_hash` entry with the SHA-256 hash of each section is stored next to
+    /// `_versions`. When a section no longer matches its hash on the next load, the edit is
+    /// logged and handled according to `policy`.
+    pub fn with_external_edit_detection(mut self, policy: ExternalEditPolicy) -> Self {
+        self.storage.external_edit_policy = Some(policy);
+        self
+    }
+
+    /// Only load and save the listed sections of the settings file.
+    ///
+    /// Sections are named by the lowercase type key, e.g. `"audiosettings"` for
+    /// `AudioSettings`. Other sections, such as those left behind by settings types that are no
+    /// longer registered, are ignored on load and dropped from the file on the next save.
+    pub fn with_section_whitelist(mut self, sections: &[&str]) -> Self {
+        self.storage.section_whitelist =
+            Some(sections.iter().map(|section| section.to_string()).collect());
+        self
+    }
+
+    /// Ignore the listed sections of the settings file.
+    ///
+    /// The sections are dropped when the file is read, so their values never reach the
+    /// resources, and are left out of the file on the next save. Takes precedence over
+    /// [`with_section_whitelist`](Self::with_section_whitelist).
+    pub fn with_section_blacklist(mut self, sections: &[&str]) -> Self {
+        self.storage.section_blacklist =
+            sections.iter().map(|section| section.to_string()).collect();
+        self
+    }
+
+    /// Choose the order of the object keys in written JSON files.
+    ///
+    /// [`JsonSortMode::Alphabetical`] sorts the keys at every nesting level, including the
+    /// sections, which keeps diffs of settings files stable. Defaults to [`JsonSortMode::None`].
+    pub fn with_json_sorting(mut self, mode: JsonSortMode) -> Self {
+        self.storage.json_sort = mode;
+        self
+    }
+
+    /// Refuse to load settings files larger than `bytes`.
+    ///
+    /// The size is checked before the file is read, so a corrupt or malicious file can't exhaust
+    /// memory. Oversized files are ignored with a warning and the defaults are used. For
+    /// compressed files the limit also applies to the decompressed size, which is limited to
+    /// 64 MiB without this setting.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.storage.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Validate loaded settings field by field.
+    ///
+    /// Without validation, a single invalid value in a hand-edited file makes the whole section
+    /// fall back to defaults. With validation, only fields that are unknown or can't be
+    /// deserialized (wrong type, out of range, ...) are replaced by their defaults, with a
+    /// warning for each of them.
+    ///
+    /// Settings types that provide a JSON Schema via [`Settings::schema`] are additionally
+    /// checked against it, and fall back to their defaults entirely if the check fails.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.schema_validation = true;
+        self
+    }
+
+    /// Write changes to disk after `delay` instead of in the same frame.
+    ///
+    /// Changes made while a write is pending are included in that write. Pending changes are
+    /// flushed in the `Last` schedule, and immediately when the app exits.
+    pub fn with_write_delay(mut self, delay: Duration) -> Self {
+        self.write_delay = Some(delay);
+        self
+    }
+
+    /// Override settings with environment variables named `{PREFIX}_{SECTION}_{FIELD}`.
+    ///
+    /// For example `MYGAME_AUDIO_MASTER_VOLUME=0.5` sets `master_volume` of `AudioSettings`
+    /// with the prefix `MYGAME`. The section is the section name in upper case, with or without
+    /// a trailing `SETTINGS`, and nested fields are separated by a double underscore, as in
+    /// `MYGAME_GRAPHICS_RESOLUTION__WIDTH=1920`. Values are parsed as JSON scalars, anything
+    /// else is used as a string.
+    ///
+    /// Values are looked up in the order defaults → file delta → environment. Overrides are
+    /// never saved: the settings file keeps its values unless a field is changed at runtime.
+    pub fn with_env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Read the variables of [`with_env_prefix`](Self::with_env_prefix) from `vars` instead of
+    /// the environment of the process.
+    pub fn with_env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env_vars = Some(vars.into_iter().collect());
+        self
+    }
+
+    /// Override settings with command-line arguments like `--setting.{section}.{field}=value`.
+    ///
+    /// For example `--setting.audio.master_volume=0.5` sets `master_volume` of `AudioSettings`.
+    /// The section is matched like in [`with_env_prefix`](Self::with_env_prefix), nested fields
+    /// are separated by dots and values are parsed the same way. Other arguments are ignored.
+    ///
+    /// Arguments are applied after environment variables and, like them, never saved. The
+    /// settings resources are inserted with the overrides applied, so they aren't reported as
+    /// changed.
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        self.args = args.into_iter().collect();
+        self
+    }
+
+    /// Override settings with the command-line arguments of the process, see
+    /// [`with_args`](Self::with_args).
+    pub fn with_process_args(self) -> Self {
+        self.with_args(std::env::args())
+    }
+
+    /// Write changes to disk once no settings changed for `debounce`.
+    ///
+    /// Batches rapid changes, like dragging a slider, into a single write. Every change of a
+    /// registered settings type restarts the wait. Combined with
+    /// [`with_write_delay`](Self::with_write_delay), changes are written after the delay at
+    /// the latest. Pending changes are flushed when the app exits, or any time with
+    /// [`flush_settings_now`](crate::flush_settings_now).
+    pub fn with_save_debounce(mut self, debounce: Duration) -> Self {
+        self.save_debounce = Some(debounce);
+        self
+    }
+
+    /// Keep the last `depth` values of every settings type for undo and redo.
+    ///
+    /// Inserts a [`SettingsHistory`] resource per type, which records the previous value on
+    /// every save. Use [`SettingsCommandsExt::undo_settings`](crate::SettingsCommandsExt::undo_settings)
+    /// and [`SettingsCommandsExt::redo_settings`](crate::SettingsCommandsExt::redo_settings)
+    /// to restore values.
+    pub fn with_history_depth(mut self, depth: usize) -> Self {
+        self.history_depth = Some(depth);
+        self
+    }
+
+    /// Pre-allocate the internal settings maps for `capacity` sections.
+    ///
+    /// By default the maps are sized for the number of registered types. A larger hint avoids
+    /// rehashing when the file contains many more sections, e.g. from other plugins.
+    pub fn with_initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = Some(capacity);
+        self
+    }
+
     pub fn register<T: Settings + 'static>(mut self) -> Self {
         let handler = Box::new(TypedSettingsHandler::<T>::new());
-        self.handlers.push(handler);
+        self.add_handler(handler);
         self
     }
+
+    /// Register a settings type that is loaded on the first frame instead of during startup.
+    ///
+    /// Until the settings are loaded, a [`SettingsLoadPending<T>`] marker resource is present
+    /// instead of `T`. The settings are loaded in `PreUpdate`, so systems in `Update` and later
+    /// can access `Res<T>` as usual. Startup systems must not request `Res<T>`.
+    pub fn register_lazy<T: Settings + 'static>(mut self) -> Self {
+        let mut handler = TypedSettingsHandler::<T>::new();
+        handler.lazy = true;
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Register a settings type whose defaults are produced by `default_fn` instead of
+    /// `T::default()`.
+    ///
+    /// Use this when sensible defaults depend on runtime data, e.g. the current monitor
+    /// resolution. Deltas are computed against and merged onto the value returned by `default_fn`.
+    pub fn register_with_default_fn<T: Settings + 'static>(
+        mut self,
+        default_fn: fn() -> T,
+    ) -> Self {
+        let mut handler = TypedSettingsHandler::<T>::new();
+        handler.config.default_fn = default_fn;
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Register a settings type that can also be read from threads without ECS access.
+    ///
+    /// Besides the `T` resource, a [`SharedSettings<T>`](crate::SharedSettings) resource is
+    /// inserted, which holds a copy behind an `Arc<RwLock<T>>`. Modify the `T` resource as
+    /// usual, the copy is updated in `PostUpdate` after the changes were saved.
+    pub fn register_shared<T: Settings + 'static>(mut self) -> Self {
+        let handler = crate::shared::SharedSettingsHandler::new(TypedSettingsHandler::<T>::new());
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Register a settings type that only exists while the app is in `state`.
+    ///
+    /// The settings are loaded from disk when entering the state and inserted as a resource.
+    /// While in the state they are saved on change like other settings. When leaving the state
+    /// they are saved and the resource is removed. Useful for per-level editor settings.
+    #[cfg(feature = "bevy_state")]
+    pub fn register_for_state<T: Settings + 'static, S: bevy::state::state::States>(
+        mut self,
+        state: S,
+    ) -> Self {
+        let handler =
+            crate::state::StateSettingsHandler::new(TypedSettingsHandler::<T>::new(), state);
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Register a settings type that is loaded from a bundled asset instead of the settings
+    /// file.
+    ///
+    /// The asset at `path` must have the `.settings.json` extension. Its contents are merged
+    /// onto the defaults like a stored delta. Until the asset is loaded, a
+    /// [`SettingsLoadPending<T>`] marker resource is present instead of `T`; the settings are
+    /// inserted in `PreUpdate` once loading finished, or defaults if it failed. Bundled settings
+    /// are read-only, changes aren't written to the settings file.
+    ///
+    /// Requires the `AssetPlugin` to be added before the `SettingsPlugin`.
+    #[cfg(feature = "bevy_asset")]
+    pub fn register_as_asset<T: Settings + 'static>(
+        mut self,
+        path: impl Into<bevy::asset::AssetPath<'static>>,
+    ) -> Self {
+        let handler = crate::asset::AssetSettingsHandler::new(
+            path.into(),
+            TypedSettingsHandler::<T>::new().config,
+        );
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Register a settings type with a schema version and a migration for older files.
+    ///
+    /// The version of each settings type is stored in the `_versions` section of the file. When
+    /// the stored version is older than `version`, the matching [`Settings::migrations`] are
+    /// applied to the stored delta before it is merged with the defaults, or `migrate` is called
+    /// if none of them match. The stored version is updated to `version` afterwards, even if the
+    /// migration didn't change anything. Sections without a stored version, e.g. saved before the
+    /// type was versioned, are migrated from [`IMPLICIT_INITIAL_VERSION`].
+    ///
+    /// If `version` is not a valid semver version, a warning is logged and the type is
+    /// registered without a version.
+    ///
+    /// # Panics
+    /// Panics in debug builds if `version` is empty. Use
+    /// [`SettingsPlugin::register_optional_version`] if the version may be missing.
+    pub fn register_with_version<T: Settings + 'static>(
+        self,
+        version: &str,
+        migrate: MigrationFn,
+    ) -> Self {
+        debug_assert!(
+            !version.is_empty(),
+            "empty settings version for {}, use register_optional_version instead",
+            T::type_name()
+        );
+
+        self.register_optional_version::<T>(Some(version), migrate)
+    }
+
+    /// Register a settings type with an optional schema version.
+    ///
+    /// With `Some` version this behaves like [`SettingsPlugin::register_with_version`]. With
+    /// `None` the type is registered without a version and `migrate` is never called.
+    pub fn register_optional_version<T: Settings + 'static>(
+        mut self,
+        version: Option<&str>,
+        migrate: MigrationFn,
+    ) -> Self {
+        let version = version.and_then(|version| match semver::Version::parse(version) {
+            Ok(version) => Some(version),
+            Err(e) => {
+                warn!(
+                    "Invalid settings version '{}' for {}: {}. Registering without a version.",
+                    version,
+                    T::type_name(),
+                    e
+                );
+                None
+            }
+        });
+
+        let mut handler = TypedSettingsHandler::<T>::new();
+        handler.config.migrate = version.as_ref().map(|_| migrate);
+        handler.config.version = version;
+        self.add_handler(Box::new(handler));
+        self
+    }
+
+    /// Check changes of `T` with `validator` before they are saved.
+    ///
+    /// When a validator returns an error, the message is logged, the change is reverted to the
+    /// last accepted settings and a [`SettingsValidationFailed`] message is sent, so invalid
+    /// settings never reach the disk. A type can have several validators, which must all pass.
+    /// Loaded settings aren't validated.
+    pub fn register_validator<T, F>(mut self, validator: F) -> Self
+    where
+        T: Settings,
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    {
+        let validator = Arc::new(validator);
+        self.validators.push(Arc::new(move |app: &mut App| {
+            app.init_resource::<SettingsValidators<T>>();
+            app.world_mut()
+                .resource_mut::<SettingsValidators<T>>()
+                .0
+                .push(validator.clone());
+        }));
+        self
+    }
+
+    /// Add a handler, making sure no other registered type uses the same section
+    ///
+    /// Two types with the same section would overwrite each other's settings in the file. Debug
+    /// builds panic, release builds log an error and don't register the second type.
+    fn add_handler(&mut self, handler: Box<dyn SettingsHandler>) {
+        let type_key = handler.type_key();
+        if self.handlers.iter().any(|h| h.type_key() == type_key) {
+            let message = format!(
+                "Settings section '{}' is already used by another registered type, settings types \
+                 must have unique type names",
+                type_key
+            );
+            if cfg!(debug_assertions) {
+                panic!("{}", message);
+            }
+            error!("{}. Ignoring the second type.", message);
+            return;
+        }
+
+        self.handlers.push(handler);
+    }
+
+    /// Get the schema version a settings type was registered with, if any
+    ///
+    /// Returns `None` if the type was registered without a version or the plugin hasn't been
+    /// added to the app.
+    pub fn registered_version<T: Settings>(world: &World) -> Option<semver::Version> {
+        let manager = world.get_resource::<SettingsManager>()?;
+        let target_versions = manager.target_versions.lock().unwrap();
+        target_versions.get(&get_type_key::<T>()).cloned()
+    }
+}
+
+/// Marker resource present while a lazily registered settings type has not been loaded yet
+///
+/// See [`SettingsPlugin::register_lazy`].
+#[derive(Resource)]
+pub struct SettingsLoadPending<T: Settings> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Settings> Default for SettingsLoadPending<T> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
 }
 
 impl Default for SettingsPlugin {
@@ -79,66 +776,564 @@ impl Default for SettingsPlugin {
 }
 
 /// Internal trait for type-erased settings operations
-trait SettingsHandler: Send + Sync {
-    fn load_and_insert(&self, app: &mut App, storage: &Storage);
+pub(crate) trait SettingsHandler: Send + Sync {
+    /// Section name of the settings type in the file
+    fn type_key(&self) -> String;
+    /// JSON Schema of the stored delta, see [`Settings::schema`]
+    fn schema(&self) -> Option<Value>;
+    /// Whether loading is deferred until the first frame
+    fn is_lazy(&self) -> bool;
+    /// Load the settings and insert them as a resource
+    ///
+    /// Returns whether the settings file needs to be rewritten, e.g. after a migration.
+    fn load_and_insert(
+        &self,
+        app: &mut App,
+        manager: &SettingsManager,
+        all_settings: &Map<String, Value>,
+        log: &mut MigrationLog,
+        version_info: &mut SettingsVersionInfo,
+    ) -> bool;
     fn register_save_system(&self, app: &mut App);
 }
 
 /// Concrete implementation of SettingsHandler for a specific type
-struct TypedSettingsHandler<T: Settings> {
-    _phantom: PhantomData<T>,
+pub(crate) struct TypedSettingsHandler<T: Settings> {
+    /// Defer loading until the first `PreUpdate`
+    lazy: bool,
+    pub(crate) config: SettingsTypeConfig<T>,
 }
 
 impl<T: Settings> TypedSettingsHandler<T> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
+        debug_assert!(
+            is_valid_type_key(&get_type_key::<T>()),
+            "Invalid settings section name '{}' for {}: section names may only contain ASCII \
+             letters, digits, '_' and '-', and must not start with '_'",
+            get_type_key::<T>(),
+            T::type_name()
+        );
+
         Self {
-            _phantom: PhantomData,
+            lazy: false,
+            config: SettingsTypeConfig {
+                default_fn: T::default,
+                version: None,
+                migrate: None,
+            },
         }
     }
 }
 
 impl<T: Settings> SettingsHandler for TypedSettingsHandler<T> {
-    fn load_and_insert(&self, app: &mut App, storage: &Storage) {
-        let type_key = get_type_key::<T>();
+    fn type_key(&self) -> String {
+        get_type_key::<T>()
+    }
 
-        // Load all settings from file
-        let all_settings = storage.load_all().unwrap_or_else(|e| {
-            warn!("Failed to load settings: {}. Using defaults.", e);
-            serde_json::Map::new()
-        });
+    fn schema(&self) -> Option<Value> {
+        T::schema()
+    }
 
-        // Get delta for this type and merge with defaults
-        let delta = all_settings.get(&type_key);
-        let settings = merge_with_defaults::<T>(delta).unwrap_or_else(|e| {
-            warn!(
-                "Failed to merge settings for {}: {}. Using defaults.",
-                T::type_name(),
-                e
-            );
-            T::default()
-        });
+    fn is_lazy(&self) -> bool {
+        self.lazy
+    }
+
+    fn load_and_insert(
+        &self,
+        app: &mut App,
+        manager: &SettingsManager,
+        all_settings: &Map<String, Value>,
+        log: &mut MigrationLog,
+        version_info: &mut SettingsVersionInfo,
+    ) -> bool {
+        insert_config(app, manager, &self.config);
+
+        if self.lazy {
+            app.init_resource::<SettingsLoadPending<T>>();
+            return false;
+        }
 
         // Insert as resource
+        let (settings, needs_save) =
+            load_settings(&self.config, manager, all_settings, log, version_info);
+        app.world_mut().write_message(SettingsLoadedEvent {
+            value: settings.clone(),
+            source: manager.load_source::<T>(),
+        });
         app.insert_resource(settings);
+        needs_save
     }
 
     fn register_save_system(&self, app: &mut App) {
-        app.add_systems(PostUpdate, save_settings_on_change::<T>);
+        if self.lazy {
+            app.add_systems(
+                PreUpdate,
+                load_pending_settings::<T>.run_if(resource_exists::<SettingsLoadPending<T>>),
+            );
+            app.add_systems(
+                PostUpdate,
+                save_settings_on_change::<T>.run_if(resource_exists::<T>),
+            );
+        } else {
+            app.add_systems(PostUpdate, save_settings_on_change::<T>);
+        }
+
+        if app.world().contains_resource::<HotReload>() {
+            app.add_systems(
+                PostUpdate,
+                hot_reload_settings::<T>
+                    .after(receive_hot_reload)
+                    .before(save_settings_on_change::<T>)
+                    .run_if(resource_exists::<T>),
+            );
+        }
     }
 }
 
+/// Insert the per-type configuration and record the registered version
+pub(crate) fn insert_config<T: Settings>(
+    app: &mut App,
+    manager: &SettingsManager,
+    config: &SettingsTypeConfig<T>,
+) {
+    app.insert_resource(config.clone());
+    app.add_message::<SettingsChanged<T>>();
+    app.add_message::<SettingsLoadedEvent<T>>();
+    if let Some(depth) = manager.history_depth {
+        app.insert_resource(SettingsHistory::<T>::new(depth));
+    }
+
+    if let Some(version) = &config.version {
+        manager
+            .target_versions
+            .lock()
+            .unwrap()
+            .insert(get_type_key::<T>(), version.clone());
+    }
+}
+
+/// Read all settings from the storage, falling back to an empty map on errors
+fn read_all_settings(storage: &Storage, stats: &mut StorageStats) -> Map<String, Value> {
+    storage.load_all(stats).unwrap_or_else(|e| {
+        warn!("Failed to load settings: {}. Using defaults.", e);
+        Map::new()
+    })
+}
+
+/// Load a settings type from the file contents, migrating it if it was saved with an older
+/// schema version
+///
+/// The resulting delta and version are tracked in the manager. Returns the settings and whether
+/// the file needs to be rewritten.
+fn load_settings<T: Settings>(
+    config: &SettingsTypeConfig<T>,
+    manager: &SettingsManager,
+    all_settings: &Map<String, Value>,
+    log: &mut MigrationLog,
+    version_info: &mut SettingsVersionInfo,
+) -> (T, bool) {
+    let type_key = get_type_key::<T>();
+    let mut needs_save = false;
+    let mut info = VersionInfo {
+        target_version: config.version.clone(),
+        ..default()
+    };
+
+    // Get delta for this type
+    let mut delta = all_settings.get(&type_key).cloned();
+    if let Some(value) = &delta {
+        manager
+            .storage
+            .observe(|| SettingsObservabilityEvent::Load {
+                section: type_key.clone(),
+                version: all_settings
+                    .get(VERSIONS_KEY)
+                    .and_then(|versions| versions.get(&type_key))
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                bytes: json_size(value),
+            });
+    }
+
+    if let Some(target) = &config.version {
+        let stored_version = all_settings
+            .get(VERSIONS_KEY)
+            .and_then(|versions| versions.get(&type_key))
+            .and_then(Value::as_str)
+            .filter(|version| !version.is_empty())
+            .and_then(|version| match semver::Version::parse(version) {
+                Ok(version) => Some(version),
+                Err(_) => {
+                    warn!(
+                        "Unparseable stored version '{}' for section '{}', treating as None",
+                        version, type_key
+                    );
+                    None
+                }
+            });
+
+        info.file_version = stored_version.clone();
+
+        // Sections saved before the type was versioned are migrated from the initial version
+        let from = &stored_version.clone().unwrap_or(IMPLICIT_INITIAL_VERSION);
+        let mut migration_failed = false;
+
+        if let (Some(migrate), Some(value)) = (config.migrate, delta.as_ref()) {
+            if from < target {
+                let mut migrated = value.clone();
+                // Fields added, removed or renamed in later versions are handled first
+                let fields_changed = T::migrate_fields(from, &mut migrated);
+                let result = run_versioned_migrations(T::migrations(), from, target, &mut migrated)
+                    .unwrap_or_else(|| migrate(from, &mut migrated));
+                match result.map(|changed| changed || fields_changed) {
+                    Ok(changed) => {
+                        manager
+                            .storage
+                            .observe(|| SettingsObservabilityEvent::MigrationRan {
+                                section: type_key.clone(),
+                                from: from.clone(),
+                                to: target.clone(),
+                                changed,
+                            });
+                        info!(
+                            "Migrated settings for {} from {} to {}",
+                            T::type_name(),
+                            from,
+                            target
+                        );
+                        if let Some(changes) = compute_value_delta(&migrated, value) {
+                            if manager.migration_dry_run {
+                                info!(
+                                    "Migration dry run for {}, changes: {}",
+                                    T::type_name(),
+                                    redact::<T>(&changes)
+                                );
+                            }
+                            log.entries.push(MigrationLogEntry {
+                                type_name: T::type_name(),
+                                from_version: Some(from.clone()),
+                                to_version: target.clone(),
+                                changes,
+                            });
+                        }
+                        delta = Some(migrated);
+                        needs_save |= changed;
+                        info.migrated = true;
+                    }
+                    Err(e) => {
+                        migration_failed = true;
+                        match manager.migration_error_policy {
+                            MigrationErrorPolicy::WarnAndUseDelta => {
+                                warn!(
+                                    "Failed to migrate settings for {}: {}. Using unmigrated values.",
+                                    T::type_name(),
+                                    e
+                                );
+                            }
+                            MigrationErrorPolicy::WarnAndUseDefaults => {
+                                warn!(
+                                    "Failed to migrate settings for {}: {}. Using defaults.",
+                                    T::type_name(),
+                                    e
+                                );
+                                delta = None;
+                            }
+                            MigrationErrorPolicy::Panic => {
+                                panic!("Failed to migrate settings for {}: {}", T::type_name(), e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        match &stored_version {
+            Some(from) if from > target => {
+                warn!(
+                    "Settings for {} were saved by a newer version ({}) than the current binary ({}). Some settings may be ignored.",
+                    T::type_name(),
+                    from,
+                    target
+                );
+                // The newer section is kept in the file, so it is still there after upgrading
+                if manager.downgrade_policy == DowngradePolicy::UseDefaults {
+                    delta = None;
+                    manager
+                        .protected_sections
+                        .lock()
+                        .unwrap()
+                        .insert(type_key.clone());
+                }
+            }
+            // Keep the stored version, so the migration runs again on the next load
+            _ if migration_failed => {}
+            _ => {
+                // Always record the target version, even if the migration didn't change
+                // anything, so the migration isn't run again on the next load
+                needs_save |= stored_version.as_ref() != Some(target);
+                manager
+                    .versions
+                    .lock()
+                    .unwrap()
+                    .insert(type_key.clone(), target.to_string());
+            }
+        }
+    }
+
+    // Fields loaded through a `#[serde(alias)]` are stored under their new name from now on
+    if let Some(value) = delta.as_mut() {
+        if apply_serde_aliases::<T>(value) {
+            info!("Renamed aliased fields in settings for {}", T::type_name());
+            needs_save = true;
+        }
+    }
+
+    if manager.schema_validation {
+        if let (Some(schema), Some(value)) = (T::schema(), delta.as_ref()) {
+            if let Err(errors) = check_schema(&schema, value) {
+                let descriptions: Vec<String> = errors
+                    .iter()
+                    .map(|(path, error)| format!("{} at '{}'", error, path))
+                    .collect();
+                warn!(
+                    "Settings for {} don't match the schema: {}. Using defaults.",
+                    T::type_name(),
+                    descriptions.join(", ")
+                );
+                for (path, error) in errors {
+                    manager
+                        .storage
+                        .observe(|| SettingsObservabilityEvent::ValidationFailed {
+                            section: type_key.clone(),
+                            field: (!path.is_empty()).then_some(path),
+                            error,
+                        });
+                }
+                delta = None;
+            }
+        }
+
+        delta = delta.map(|delta| {
+            validate_delta(
+                &(config.default_fn)(),
+                &delta,
+                manager.storage.merge_options,
+                manager.storage.observer.as_deref().map(|o| o as _),
+            )
+        });
+    }
+
+    // Merge delta with defaults
+    let merged = merge_with_base(
+        (config.default_fn)(),
+        delta.as_ref(),
+        manager.storage.merge_options,
+    );
+    let settings = merged.unwrap_or_else(|e| {
+        warn!(
+            "Failed to merge settings for {}: {}. Using defaults.",
+            T::type_name(),
+            e
+        );
+        (config.default_fn)()
+    });
+
+    manager.update_delta(&settings, &(config.default_fn)());
+    version_info.sections.insert(T::type_name(), info);
+    (manager.apply_overrides(settings), needs_save)
+}
+
+/// Read the settings file and load a settings type from it at runtime
+///
+/// Used for types that aren't loaded while building the plugin.
+pub(crate) fn load_settings_from_disk<T: Settings>(
+    config: &SettingsTypeConfig<T>,
+    manager: &SettingsManager,
+    stats: &mut StorageStats,
+    log: &mut MigrationLog,
+    version_info: &mut SettingsVersionInfo,
+) -> T {
+    let all_settings = read_all_settings(&manager.storage, stats);
+    manager.seed(&all_settings);
+
+    let (settings, needs_save) = load_settings(config, manager, &all_settings, log, version_info);
+    if needs_save {
+        if let Err(e) = manager.save(stats) {
+            error!("Failed to save settings: {}", e);
+        }
+    }
+
+    settings
+}
+
+/// System that loads a lazily registered settings type and removes its pending marker
+fn load_pending_settings<T: Settings>(
+    mut commands: Commands,
+    config: Res<SettingsTypeConfig<T>>,
+    manager: Res<SettingsManager>,
+    mut stats: ResMut<StorageStats>,
+    mut log: ResMut<MigrationLog>,
+    mut version_info: ResMut<SettingsVersionInfo>,
+    mut loaded: MessageWriter<SettingsLoadedEvent<T>>,
+) {
+    let settings =
+        load_settings_from_disk(&config, &manager, &mut stats, &mut log, &mut version_info);
+    loaded.write(SettingsLoadedEvent {
+        value: settings.clone(),
+        source: manager.load_source::<T>(),
+    });
+    commands.insert_resource(settings);
+    commands.remove_resource::<SettingsLoadPending<T>>();
+}
+
+/// System that replaces the settings with the values of a hot reloaded settings file
+fn hot_reload_settings<T: Settings>(
+    reload: Res<HotReload>,
+    mut settings: ResMut<T>,
+    config: Res<SettingsTypeConfig<T>>,
+    manager: Res<SettingsManager>,
+    mut log: ResMut<MigrationLog>,
+    mut version_info: ResMut<SettingsVersionInfo>,
+) {
+    let Some(all_settings) = &reload.settings else {
+        return;
+    };
+
+    // Migrated values are saved along with the change
+    let (reloaded, _) = load_settings(&config, &manager, all_settings, &mut log, &mut version_info);
+    if settings.set_if_neq(reloaded) {
+        info!("Hot reloaded settings for {}", T::type_name());
+    }
+}
+
+/// Write a JSON Schema describing the settings file of `handlers` to `path`
+fn write_schema_file(path: &Path, handlers: &[Box<dyn SettingsHandler>]) -> std::io::Result<()> {
+    let properties: Map<String, Value> = handlers
+        .iter()
+        .map(|handler| {
+            let schema = handler
+                .schema()
+                .unwrap_or_else(|| Value::Object(Map::new()));
+            (handler.type_key(), schema)
+        })
+        .collect();
+    let schema = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": properties,
+    });
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&schema)?)
+}
+
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
-        let storage = self.storage.clone();
+        let capacity = self.initial_capacity.unwrap_or(self.handlers.len());
+        let mut storage = self.storage.clone();
+        storage.section_order = self.handlers.iter().map(|h| h.type_key()).collect();
+        let mut manager = SettingsManager::new(storage, capacity);
+        manager.migration_error_policy = self.migration_error_policy;
+        manager.downgrade_policy = self.downgrade_policy;
+        manager.write_delay = self.write_delay;
+        manager.save_debounce = self.save_debounce;
+        manager.history_depth = self.history_depth;
+        let sections: Vec<String> = self.handlers.iter().map(|h| h.type_key()).collect();
+        if let Some(prefix) = &self.env_prefix {
+            manager.overrides = match &self.env_vars {
+                Some(vars) => env_overrides(prefix, &sections, vars.iter().cloned()),
+                None => env_overrides(prefix, &sections, std::env::vars()),
+            };
+            for section in manager.overrides.keys() {
+                manager
+                    .override_sources
+                    .insert(section.clone(), SettingsChangeSource::EnvOverride);
+            }
+        }
+        for (section, overrides) in arg_overrides(&sections, self.args.iter().cloned()) {
+            manager
+                .override_sources
+                .insert(section.clone(), SettingsChangeSource::ArgOverride);
+            manager
+                .overrides
+                .entry(section)
+                .or_default()
+                .extend(overrides);
+        }
+        manager.schema_validation = self.schema_validation;
+        manager.migration_dry_run = self.migration_dry_run;
+        let mut stats = StorageStats::default();
+        let mut log = MigrationLog::default();
+        let mut version_info = SettingsVersionInfo::default();
+
+        // Lazily registered types read the file themselves on the first frame
+        let all_settings = if self.handlers.iter().any(|handler| !handler.is_lazy()) {
+            read_all_settings(&manager.storage, &mut stats)
+        } else {
+            Map::new()
+        };
+        manager.seed(&all_settings);
 
+        let mut needs_save = false;
         for handler in &self.handlers {
-            handler.load_and_insert(app, &storage);
+            needs_save |=
+                handler.load_and_insert(app, &manager, &all_settings, &mut log, &mut version_info);
         }
 
-        app.insert_resource(SettingsManager {
-            storage,
-            settings_map: Arc::new(Mutex::new(HashMap::new())),
-        });
+        if needs_save {
+            if let Err(e) = manager.save(&mut stats) {
+                error!("Failed to save settings: {}", e);
+            }
+        }
+
+        if let Some(path) = &self.schema_output {
+            if let Err(e) = write_schema_file(path, &self.handlers) {
+                error!(
+                    "Failed to write settings schema to {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+
+        app.add_message::<SettingsFileChanged>();
+        app.add_message::<SettingsValidationFailed>();
+        for insert_validator in &self.validators {
+            insert_validator(app);
+        }
+        if let Some(interval) = self.watch_interval {
+            let path = manager.storage.get_path();
+            app.insert_resource(SettingsFileWatch::new(
+                interval,
+                self.file_watcher_debounce,
+                &path,
+                &stats,
+            ));
+            app.add_systems(PreUpdate, poll_settings_file);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.hot_reload {
+            match HotReload::spawn(manager.storage.get_path(), self.file_watcher_debounce) {
+                Ok(reload) => {
+                    app.insert_resource(reload);
+                    app.add_systems(PostUpdate, receive_hot_reload);
+                }
+                Err(e) => error!("Failed to start watching the settings file: {}", e),
+            }
+        }
+
+        app.insert_resource(manager);
+        app.insert_resource(stats);
+        app.insert_resource(log);
+        app.insert_resource(version_info);
+        app.init_resource::<PendingSave>();
+
+        if self.write_delay.is_some() || self.save_debounce.is_some() {
+            app.add_systems(Last, flush_pending_save);
+        }
 
         for handler in &self.handlers {
             handler.register_save_system(app);
@@ -0,0 +1,94 @@
+use crate::{
+    migration::{MigrationLog, SettingsVersionInfo},
+    plugin::{SettingsHandler, TypedSettingsHandler},
+    storage::{save_settings_on_change, SettingsManager},
+    Settings,
+};
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+/// Copy of a settings resource that can be read from threads without ECS access
+///
+/// Inserted next to `T` by
+/// [`SettingsPlugin::register_shared`](crate::SettingsPlugin::register_shared). Changes to the
+/// `T` resource are copied into it in `PostUpdate`, after they were saved. Clone the
+/// [`handle`](Self::handle) and move it to e.g. an audio thread.
+#[derive(Resource)]
+pub struct SharedSettings<T: Settings>(Arc<RwLock<T>>);
+
+impl<T: Settings> SharedSettings<T> {
+    /// Get a handle to the shared settings
+    pub fn handle(&self) -> Arc<RwLock<T>> {
+        self.0.clone()
+    }
+
+    /// Lock the shared settings for reading
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Settings handler that also inserts a [`SharedSettings`] copy of the resource
+pub(crate) struct SharedSettingsHandler<T: Settings> {
+    inner: TypedSettingsHandler<T>,
+}
+
+impl<T: Settings> SharedSettingsHandler<T> {
+    pub(crate) fn new(inner: TypedSettingsHandler<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Settings> SettingsHandler for SharedSettingsHandler<T> {
+    fn type_key(&self) -> String {
+        self.inner.type_key()
+    }
+
+    fn schema(&self) -> Option<Value> {
+        self.inner.schema()
+    }
+
+    fn is_lazy(&self) -> bool {
+        false
+    }
+
+    fn load_and_insert(
+        &self,
+        app: &mut App,
+        manager: &SettingsManager,
+        all_settings: &Map<String, Value>,
+        log: &mut MigrationLog,
+        version_info: &mut SettingsVersionInfo,
+    ) -> bool {
+        let needs_save = self
+            .inner
+            .load_and_insert(app, manager, all_settings, log, version_info);
+        let settings = app.world().resource::<T>().clone();
+        app.insert_resource(SharedSettings(Arc::new(RwLock::new(settings))));
+        needs_save
+    }
+
+    fn register_save_system(&self, app: &mut App) {
+        self.inner.register_save_system(app);
+        app.add_systems(
+            PostUpdate,
+            sync_shared_settings::<T>.after(save_settings_on_change::<T>),
+        );
+    }
+}
+
+/// System that copies changed settings into their [`SharedSettings`]
+fn sync_shared_settings<T: Settings>(settings: Res<T>, shared: Res<SharedSettings<T>>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let mut shared = shared
+        .0
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *shared = settings.clone();
+}
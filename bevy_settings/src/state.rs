@@ -0,0 +1,98 @@
+use crate::{
+    messages::SettingsLoadedEvent,
+    migration::{MigrationLog, SettingsVersionInfo},
+    plugin::{insert_config, load_settings_from_disk, SettingsHandler, TypedSettingsHandler},
+    storage::{save_settings_on_change, SettingsManager, SettingsTypeConfig, StorageStats},
+    Settings,
+};
+use bevy::prelude::*;
+use bevy::state::condition::in_state;
+use bevy::state::state::{OnEnter, OnExit, States};
+use serde_json::{Map, Value};
+
+/// Settings handler that loads and saves a settings type on state transitions
+pub(crate) struct StateSettingsHandler<T: Settings, S: States> {
+    inner: TypedSettingsHandler<T>,
+    state: S,
+}
+
+impl<T: Settings, S: States> StateSettingsHandler<T, S> {
+    pub(crate) fn new(inner: TypedSettingsHandler<T>, state: S) -> Self {
+        Self { inner, state }
+    }
+}
+
+impl<T: Settings, S: States> SettingsHandler for StateSettingsHandler<T, S> {
+    fn type_key(&self) -> String {
+        self.inner.type_key()
+    }
+
+    fn schema(&self) -> Option<Value> {
+        self.inner.schema()
+    }
+
+    fn is_lazy(&self) -> bool {
+        true
+    }
+
+    fn load_and_insert(
+        &self,
+        app: &mut App,
+        manager: &SettingsManager,
+        _all_settings: &Map<String, Value>,
+        _log: &mut MigrationLog,
+        _version_info: &mut SettingsVersionInfo,
+    ) -> bool {
+        insert_config(app, manager, &self.inner.config);
+        false
+    }
+
+    fn register_save_system(&self, app: &mut App) {
+        app.add_systems(OnEnter(self.state.clone()), load_state_settings::<T>);
+        app.add_systems(OnExit(self.state.clone()), save_state_settings::<T>);
+        app.add_systems(
+            PostUpdate,
+            save_settings_on_change::<T>
+                .run_if(in_state(self.state.clone()).and(resource_exists::<T>)),
+        );
+    }
+}
+
+/// System that loads the settings when entering their state
+fn load_state_settings<T: Settings>(
+    mut commands: Commands,
+    config: Res<SettingsTypeConfig<T>>,
+    manager: Res<SettingsManager>,
+    mut stats: ResMut<StorageStats>,
+    mut log: ResMut<MigrationLog>,
+    mut version_info: ResMut<SettingsVersionInfo>,
+    mut loaded: MessageWriter<SettingsLoadedEvent<T>>,
+) {
+    let settings =
+        load_settings_from_disk(&config, &manager, &mut stats, &mut log, &mut version_info);
+    loaded.write(SettingsLoadedEvent {
+        value: settings.clone(),
+        source: manager.load_source::<T>(),
+    });
+    commands.insert_resource(settings);
+}
+
+/// System that saves the settings and removes the resource when leaving their state
+fn save_state_settings<T: Settings>(
+    mut commands: Commands,
+    settings: Option<Res<T>>,
+    config: Res<SettingsTypeConfig<T>>,
+    manager: Res<SettingsManager>,
+    mut stats: ResMut<StorageStats>,
+) {
+    let Some(settings) = settings else {
+        return;
+    };
+
+    manager.update_delta::<T>(&settings, &(config.default_fn)());
+    if let Err(e) = manager.save(&mut stats) {
+        error!("Failed to save settings: {}", e);
+    }
+
+    commands.remove_resource::<T>();
+}
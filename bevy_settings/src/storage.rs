@@ -1,13 +1,102 @@
-use crate::{error::Result, SerializationFormat, Settings};
+use crate::{
+    encryption::{decrypt, encrypt, ENCRYPTED_EXTENSION, NONCE_LEN},
+    error::Result,
+    format::{from_value_with_options, sort_json_value, FileEncoding, JsonOptions, JsonSortMode},
+    history::SettingsHistory,
+    messages::{SettingsChangeSource, SettingsChanged},
+    migration::{DowngradePolicy, MigrationErrorPolicy, MigrationFn},
+    overrides::{apply_overrides, revert_overrides, FieldOverride},
+    telemetry::{SettingsObservabilityEvent, SettingsObserver, SettingsTelemetry},
+    validation::{SettingsValidationFailed, SettingsValidators},
+    watch::HotReload,
+    SerializationFormat, Settings,
+};
 use bevy::prelude::*;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde_json::{Map, Value};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-/// Buffer size for binary serialization (1 MB)
-const BINARY_BUFFER_SIZE: usize = 1024 * 1024;
+/// First bytes of binary settings files, followed by the null-terminated file version
+const BINARY_MAGIC: [u8; 4] = *b"BSET";
+
+/// First bytes of a gzip stream, used to detect compressed settings files
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Limit of the decompressed size of compressed settings files without a max file size
+const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Prefix of the pseudo-keys that hold the comments of the sections in JSON files
+pub(crate) const COMMENT_PREFIX: &str = "_comment_";
+
+/// Key of the section that stores the hash of each section as it was last saved
+pub(crate) const CONTENT_HASH_KEY: &str = "_content_hash";
+
+/// Key of the section that stores the schema version of each settings type
+pub(crate) const VERSIONS_KEY: &str = "_versions";
+
+/// Key of the table that stands in for `null` in TOML files, which have no null value
+const TOML_NULL_KEY: &str = "_null";
+
+/// Counters for the reads and writes of the settings file
+///
+/// Inserted as a resource by [`SettingsPlugin`](crate::SettingsPlugin) for monitoring and
+/// debugging.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct StorageStats {
+    /// Number of times the settings file was written
+    pub saves: u64,
+    /// Number of times the settings file was read
+    pub loads: u64,
+    /// Total number of bytes written
+    pub bytes_written: u64,
+    /// Total number of bytes read
+    pub bytes_read: u64,
+    /// Time of the last write
+    pub last_save: Option<Instant>,
+    /// Time of the last read
+    pub last_load: Option<Instant>,
+}
+
+/// How to react when the file on disk was written by a newer version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum VersionCheck {
+    /// Don't compare versions at all
+    #[default]
+    Disabled,
+    /// Warn about the newer file, but load it anyway
+    Lenient,
+    /// Warn about the newer file and fall back to defaults
+    Strict,
+}
+
+/// Order of the settings sections in the written file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionSortOrder {
+    /// Sort sections by name
+    Alphabetical,
+    /// Keep the order in which the settings types were registered, followed by sections of
+    /// unregistered types sorted by name
+    #[default]
+    RegistrationOrder,
+}
+
+/// What to do with sections of the settings file that were edited outside of the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExternalEditPolicy {
+    /// Log the edit and load the edited values
+    #[default]
+    Accept,
+    /// Log the edit and ignore the section, so its defaults are used
+    RejectAndUseDefaults,
+}
 
 /// Storage that saves multiple settings types to a single file
 #[derive(Clone)]
@@ -16,8 +105,55 @@ pub(crate) struct Storage {
     pub(crate) base_path: PathBuf,
     pub(crate) filename: String,
     pub(crate) version: Option<String>,
+    pub(crate) version_check: VersionCheck,
+    /// Patches the raw file contents before any section is processed
+    pub(crate) pre_load_hook: Option<PreLoadHook>,
+    /// Text encoding of JSON files
+    pub(crate) encoding: FileEncoding,
+    /// Ignore `//` and `#` comment lines in JSON files
+    pub(crate) strip_json_comments: bool,
+    /// How loaded deltas are merged into the defaults
+    pub(crate) merge_options: MergeOptions,
+    /// Refuse to read files larger than this many bytes
+    pub(crate) max_file_size: Option<u64>,
+    /// Gzip JSON files that are larger than this many bytes
+    pub(crate) compression_threshold: Option<usize>,
+    /// Guard writes with a lock file, so concurrent instances don't corrupt the file
+    pub(crate) lock_file: bool,
+    /// Order of the sections in the written file
+    pub(crate) section_sort: SectionSortOrder,
+    /// Order of the object keys in written JSON files
+    pub(crate) json_sort: JsonSortMode,
+    /// Section names of the registered settings types, in registration order
+    pub(crate) section_order: Vec<String>,
+    /// Reporter for the timings of reads and writes
+    pub(crate) telemetry: Option<Arc<dyn SettingsTelemetry + Send + Sync>>,
+    /// Receiver of structured events about individual sections
+    pub(crate) observer: Option<Arc<dyn SettingsObserver + Send + Sync>>,
+    /// Directory for the temporary file written during saves, `None` for `base_path`
+    pub(crate) temp_dir: Option<PathBuf>,
+    /// Only these sections are loaded and saved, `None` for all sections
+    pub(crate) section_whitelist: Option<Vec<String>>,
+    /// These sections are never loaded or saved
+    pub(crate) section_blacklist: Vec<String>,
+    /// Comments written above the sections of JSON files, by section name
+    pub(crate) section_comments: HashMap<String, String>,
+    /// Store a hash of each section to detect edits between runs, `None` to disable
+    pub(crate) external_edit_policy: Option<ExternalEditPolicy>,
+    /// Called with the path of the settings file after it was written for the first time
+    pub(crate) file_created_callback: Option<FileCreatedCallback>,
+    /// AES-256-GCM key the settings file is encrypted with, `None` to write plain files
+    pub(crate) encryption_key: Option<[u8; 32]>,
+    /// Set while the settings file can't be decrypted, so saves don't replace it
+    pub(crate) decryption_failed: Arc<AtomicBool>,
 }
 
+/// Hook that receives the raw top-level map of the settings file after it was read
+pub(crate) type PreLoadHook = Arc<dyn Fn(&mut Map<String, Value>) + Send + Sync>;
+
+/// Callback that receives the path of a newly created settings file
+pub(crate) type FileCreatedCallback = Arc<dyn Fn(&Path) + Send + Sync>;
+
 impl Storage {
     /// Create a new storage with the specified format
     pub(crate) fn new(filename: impl Into<String>, format: SerializationFormat) -> Self {
@@ -26,6 +162,27 @@ impl Storage {
             base_path: PathBuf::from("settings"),
             filename: filename.into(),
             version: None,
+            version_check: VersionCheck::Disabled,
+            pre_load_hook: None,
+            encoding: FileEncoding::default(),
+            strip_json_comments: false,
+            merge_options: MergeOptions::default(),
+            max_file_size: None,
+            compression_threshold: None,
+            lock_file: false,
+            section_sort: SectionSortOrder::default(),
+            json_sort: JsonSortMode::default(),
+            section_order: Vec::new(),
+            telemetry: None,
+            observer: None,
+            temp_dir: None,
+            section_whitelist: None,
+            section_blacklist: Vec::new(),
+            section_comments: HashMap::new(),
+            external_edit_policy: None,
+            file_created_callback: None,
+            encryption_key: None,
+            decryption_failed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -42,13 +199,100 @@ impl Storage {
     }
 
     /// Get the full path for the settings file
-    fn get_path(&self) -> PathBuf {
-        self.base_path
-            .join(format!("{}.{}", self.filename, self.format.extension()))
+    ///
+    /// Encrypted files get an additional `.enc` extension.
+    pub(crate) fn get_path(&self) -> PathBuf {
+        let mut name = format!("{}.{}", self.filename, self.format.extension());
+        if self.encryption_key.is_some() {
+            name = format!("{}.{}", name, ENCRYPTED_EXTENSION);
+        }
+        self.base_path.join(name)
+    }
+
+    /// Sort section names according to the configured [`SectionSortOrder`]
+    fn sorted_keys<'a>(&self, keys: impl Iterator<Item = &'a String>) -> Vec<&'a String> {
+        let mut keys: Vec<_> = keys.collect();
+        keys.sort();
+        if self.section_sort == SectionSortOrder::RegistrationOrder {
+            // Stable sort, so unregistered sections stay sorted by name at the end
+            keys.sort_by_key(|key| {
+                self.section_order
+                    .iter()
+                    .position(|registered| registered == *key)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        keys
+    }
+
+    /// Check whether a section should be loaded and saved
+    fn is_section_allowed(&self, key: &str) -> bool {
+        !self.section_blacklist.iter().any(|blocked| blocked == key)
+            && self
+                .section_whitelist
+                .as_ref()
+                .is_none_or(|whitelist| whitelist.iter().any(|allowed| allowed == key))
+    }
+
+    /// Drop the sections and their schema versions that should not be loaded
+    fn filter_sections(&self, map: &mut Map<String, Value>) {
+        map.retain(|key, _| key == VERSIONS_KEY || self.is_section_allowed(key));
+        if let Some(Value::Object(versions)) = map.get_mut(VERSIONS_KEY) {
+            versions.retain(|key, _| self.is_section_allowed(key));
+        }
+    }
+
+    /// Compare the sections with the hashes stored on the last save to detect external edits
+    ///
+    /// The hashes are removed from the map, they are computed again on save.
+    fn check_content_hashes(&self, map: &mut Map<String, Value>) {
+        let Some(Value::Object(hashes)) = map.remove(CONTENT_HASH_KEY) else {
+            return;
+        };
+        let Some(policy) = self.external_edit_policy else {
+            return;
+        };
+
+        for (section, hash) in hashes {
+            let Some(value) = map.get(&section) else {
+                continue;
+            };
+            if hash.as_str() == Some(content_hash(value).as_str()) {
+                continue;
+            }
+
+            match policy {
+                ExternalEditPolicy::Accept => info!(
+                    "External edit detected for section '{}' — accepting external changes",
+                    section
+                ),
+                ExternalEditPolicy::RejectAndUseDefaults => {
+                    warn!(
+                        "External edit detected for section '{}' — using defaults",
+                        section
+                    );
+                    map.remove(&section);
+                }
+            }
+        }
+    }
+
+    /// Report an event to the observer, the event is only created if there is one
+    pub(crate) fn observe(&self, event: impl FnOnce() -> SettingsObservabilityEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(event());
+        }
+    }
+
+    /// Get the path of the lock file that guards writes
+    fn get_lock_path(&self) -> PathBuf {
+        self.base_path.join(format!("{}.lock", self.filename))
     }
 
     /// Load all settings from the file
-    pub(crate) fn load_all(&self) -> Result<Map<String, Value>> {
+    ///
+    /// The returned map still contains the `_versions` section, if present.
+    pub(crate) fn load_all(&self, stats: &mut StorageStats) -> Result<Map<String, Value>> {
         let path = self.get_path();
 
         // If file doesn't exist, return empty map
@@ -56,29 +300,169 @@ impl Storage {
             return Ok(Map::new());
         }
 
-        let content = fs::read(&path)?;
+        if let Some(limit) = self.max_file_size {
+            let size = fs::metadata(&path)?.len();
+            if size > limit {
+                return Err(crate::error::SettingsError::FileTooLarge { size, limit });
+            }
+        }
+
+        let started = Instant::now();
+        let mut content = fs::read(&path)?;
+        let bytes = content.len();
+        stats.loads += 1;
+        stats.bytes_read += content.len() as u64;
+        stats.last_load = Some(Instant::now());
+
+        // A file that can't be decrypted is kept for a retry with the right key, and saves are
+        // skipped until it was loaded. Only a file too short to hold a nonce is moved aside.
+        if let Some(key) = &self.encryption_key {
+            content = match decrypt(key, &content) {
+                Ok(content) => content,
+                Err(e) if content.len() < NONCE_LEN => {
+                    self.move_corrupt_file(&path);
+                    return Err(e);
+                }
+                Err(e) => {
+                    self.decryption_failed.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+            };
+            self.decryption_failed.store(false, Ordering::Relaxed);
+        }
+
+        // Large JSON files may have been compressed on save. The max file size also limits the
+        // decompressed size, so a small compressed file can't exhaust memory.
+        if self.format == SerializationFormat::Json && content.starts_with(&GZIP_MAGIC) {
+            let limit = self.max_file_size.unwrap_or(DEFAULT_MAX_DECOMPRESSED_SIZE);
+            let mut decompressed = Vec::new();
+            GzDecoder::new(content.as_slice())
+                .take(limit + 1)
+                .read_to_end(&mut decompressed)?;
+            let size = decompressed.len() as u64;
+            if size > limit {
+                return Err(crate::error::SettingsError::FileTooLarge { size, limit });
+            }
+            content = decompressed;
+        }
+
+        if self.format != SerializationFormat::Binary {
+            content = self.encoding.decode(content)?;
+        }
 
         // Deserialize based on format
         let root: Value = match self.format {
-            SerializationFormat::Json => serde_json::from_slice(&content)?,
-            SerializationFormat::Binary => {
-                let config = bincode::config::standard();
-                bincode::serde::decode_from_slice(&content, config)
-                    .map_err(crate::error::SettingsError::BincodeDecode)?
-                    .0
+            SerializationFormat::Json if self.strip_json_comments => {
+                serde_json::from_str(&strip_comment_lines(&String::from_utf8_lossy(&content)))?
             }
+            SerializationFormat::Json => serde_json::from_slice(&content)?,
+            #[cfg(feature = "yaml")]
+            SerializationFormat::Yaml => decode_yaml(&content)?,
+            SerializationFormat::Toml => decode_toml(&content)?,
+            SerializationFormat::Binary => match decode_binary(&content) {
+                Err(e @ crate::error::SettingsError::BincodeDecode(_)) => {
+                    self.move_corrupt_file(&path);
+                    return Err(e);
+                }
+                result => result?,
+            },
         };
 
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.on_load(&self.filename, bytes, started.elapsed());
+        }
+
         // Extract the settings map (skip version field)
         if let Value::Object(mut map) = root {
+            if let Some(hook) = &self.pre_load_hook {
+                hook(&mut map);
+            }
+
             // Remove version from the map (it's metadata, not settings)
-            map.remove("version");
+            let file_version = map.remove("version");
+
+            if self.is_newer_than_binary(file_version.as_ref().and_then(Value::as_str)) {
+                return Ok(Map::new());
+            }
+
+            // Comments are written again from the configuration on save
+            map.retain(|key, _| !key.starts_with(COMMENT_PREFIX));
+            self.check_content_hashes(&mut map);
+            self.filter_sections(&mut map);
             Ok(map)
         } else {
             Ok(Map::new())
         }
     }
 
+    /// Rename a settings file that can't be decoded to `<file>.corrupt`, for inspection
+    ///
+    /// A truncated binary file, e.g. after a power loss while writing, would otherwise be read
+    /// again on every start until the settings are saved. Encrypted files that fail to decrypt
+    /// are moved too, so they aren't overwritten with defaults.
+    fn move_corrupt_file(&self, path: &Path) {
+        let mut corrupt = path.as_os_str().to_owned();
+        corrupt.push(".corrupt");
+        match fs::rename(path, &corrupt) {
+            Ok(()) => warn!(
+                "Settings file {} is corrupt, moved it to {}",
+                path.display(),
+                Path::new(&corrupt).display()
+            ),
+            Err(e) => warn!(
+                "Settings file {} is corrupt and couldn't be moved: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Check whether the file version is newer than the version this binary was built with
+    ///
+    /// Returns `true` if the file contents should be discarded (strict mode only).
+    fn is_newer_than_binary(&self, file_version: Option<&str>) -> bool {
+        if self.version_check == VersionCheck::Disabled {
+            return false;
+        }
+
+        let (Some(file_version), Some(binary_version)) = (file_version, self.version.as_deref())
+        else {
+            return false;
+        };
+
+        let (Ok(file), Ok(binary)) = (
+            semver::Version::parse(file_version),
+            semver::Version::parse(binary_version),
+        ) else {
+            warn!(
+                "Could not compare settings version {} with {}, skipping version check",
+                file_version, binary_version
+            );
+            return false;
+        };
+
+        if file <= binary {
+            return false;
+        }
+
+        match self.version_check {
+            VersionCheck::Strict => {
+                warn!(
+                    "Settings file version {} is newer than {}. Using defaults.",
+                    file, binary
+                );
+                true
+            }
+            _ => {
+                warn!(
+                    "Settings file version {} is newer than {}. Loading anyway.",
+                    file, binary
+                );
+                false
+            }
+        }
+    }
+
     /// Load a specific settings type from the file
     ///
     /// This method is provided for manual control over loading. When using the plugin system,
@@ -90,8 +474,8 @@ impl Storage {
     /// # Returns
     /// Returns the merged settings (defaults + saved delta) or defaults if not found
     #[allow(dead_code)]
-    pub(crate) fn load<T: Settings>(&self, type_key: &str) -> Result<T> {
-        let all_settings = self.load_all()?;
+    pub(crate) fn load<T: Settings>(&self, type_key: &str, stats: &mut StorageStats) -> Result<T> {
+        let all_settings = self.load_all(stats)?;
 
         // Try to find settings for this type
         if let Some(value) = all_settings.get(type_key) {
@@ -103,9 +487,47 @@ impl Storage {
         }
     }
 
-    /// Save multiple settings types to the file
-    pub(crate) fn save_all(&self, settings_map: &HashMap<String, Value>) -> Result<()> {
+    /// Save multiple settings types to the file, along with their schema versions
+    pub(crate) fn save_all(
+        &self,
+        settings_map: &HashMap<String, Value>,
+        versions: &HashMap<String, String>,
+        stats: &mut StorageStats,
+    ) -> Result<()> {
         let path = self.get_path();
+        if settings_map.is_empty() && !path.exists() {
+            return Ok(());
+        }
+
+        if self.decryption_failed.load(Ordering::Relaxed) {
+            warn!(
+                "Settings file {} couldn't be decrypted, skipping save to keep it",
+                path.display()
+            );
+            return Ok(());
+        }
+
+        // Ensure directory exists
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Held until the file is written or deleted, released on drop
+        let _lock = if self.lock_file {
+            match FileLock::acquire(self.get_lock_path()) {
+                Ok(lock) => Some(lock),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    warn!(
+                        "Settings file {} is locked by another instance, skipping save",
+                        path.display()
+                    );
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        } else {
+            None
+        };
 
         // If all settings are empty (equal to defaults), delete the file
         if settings_map.is_empty() {
@@ -118,40 +540,180 @@ impl Storage {
         // Build the root object with version and all settings
         let mut root = Map::new();
 
-        // Add version if present
-        if let Some(ref version) = self.version {
+        // Add version if present, binary files store it in their header instead
+        if let (Some(version), false) = (&self.version, self.format == SerializationFormat::Binary)
+        {
             root.insert("version".to_string(), Value::String(version.clone()));
         }
 
-        // Add all settings
-        for (key, value) in settings_map {
-            root.insert(key.clone(), value.clone());
+        // Add schema versions of the individual settings types
+        if !versions.is_empty() {
+            let versions = self
+                .sorted_keys(versions.keys())
+                .into_iter()
+                .filter(|key| self.is_section_allowed(key))
+                .map(|key| (key.clone(), Value::String(versions[key].clone())))
+                .collect();
+            root.insert(VERSIONS_KEY.to_string(), Value::Object(versions));
         }
 
-        let root_value = Value::Object(root);
+        // Add the hashes of the sections, to detect edits made outside of the app
+        if self.external_edit_policy.is_some() {
+            let hashes = self
+                .sorted_keys(settings_map.keys())
+                .into_iter()
+                .filter(|key| self.is_section_allowed(key))
+                .map(|key| (key.clone(), Value::String(content_hash(&settings_map[key]))))
+                .collect();
+            root.insert(CONTENT_HASH_KEY.to_string(), Value::Object(hashes));
+        }
 
-        // Ensure directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+        // Add all settings
+        for key in self.sorted_keys(settings_map.keys()) {
+            if !self.is_section_allowed(key) {
+                continue;
+            }
+            self.observe(|| SettingsObservabilityEvent::Save {
+                section: key.clone(),
+                bytes: json_size(&settings_map[key]),
+                is_delta: true,
+            });
+            if let (Some(comment), false) = (
+                self.section_comments.get(key),
+                self.format == SerializationFormat::Binary,
+            ) {
+                root.insert(
+                    format!("{}{}", COMMENT_PREFIX, key),
+                    Value::String(comment.clone()),
+                );
+            }
+            root.insert(key.clone(), settings_map[key].clone());
         }
 
         // Serialize based on format
+        let started = Instant::now();
         let content = match self.format {
-            SerializationFormat::Json => serde_json::to_vec_pretty(&root_value)?,
-            SerializationFormat::Binary => {
-                let config = bincode::config::standard();
-                let mut buffer = vec![0u8; BINARY_BUFFER_SIZE];
-                let size = bincode::serde::encode_into_slice(&root_value, &mut buffer, config)
-                    .map_err(crate::error::SettingsError::BincodeEncode)?;
-                buffer.truncate(size);
-                buffer
+            SerializationFormat::Json => {
+                let mut root = Value::Object(root);
+                if self.json_sort == JsonSortMode::Alphabetical {
+                    sort_json_value(&mut root);
+                }
+                let json = self.encoding.encode(&serde_json::to_string_pretty(&root)?);
+                match self.compression_threshold {
+                    Some(threshold) if json.len() > threshold => {
+                        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                        encoder.write_all(&json)?;
+                        encoder.finish()?
+                    }
+                    _ => json,
+                }
             }
+            SerializationFormat::Binary => encode_binary(&root, self.version.as_deref())?,
+            #[cfg(feature = "yaml")]
+            SerializationFormat::Yaml => self
+                .encoding
+                .encode(&serde_norway::to_string(&Value::Object(root))?),
+            SerializationFormat::Toml => self.encoding.encode(&encode_toml(Value::Object(root))?),
         };
+        let content = match &self.encryption_key {
+            Some(key) => encrypt(key, &content)?,
+            None => content,
+        };
+
+        let created = !path.exists();
+        self.write_atomic(&path, &content)?;
+        if let (true, Some(callback)) = (created, &self.file_created_callback) {
+            callback(&path);
+        }
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.on_save(&self.filename, content.len(), started.elapsed());
+        }
+        stats.saves += 1;
+        stats.bytes_written += content.len() as u64;
+        stats.last_save = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Get the path of the temporary file that is written before replacing the settings file
+    ///
+    /// Each call returns a new path, so concurrent saves, e.g. from several apps in one process,
+    /// never write to the same temporary file.
+    fn get_temp_path(&self) -> PathBuf {
+        static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+        let dir = self.temp_dir.as_ref().unwrap_or(&self.base_path);
+        dir.join(format!(
+            "{}.{}.{}-{}.tmp",
+            self.filename,
+            self.format.extension(),
+            std::process::id(),
+            NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// Write `content` to a temporary file and move it over `path`
+    ///
+    /// Readers never see a half-written settings file, even if the app crashes while saving.
+    /// If the temporary directory is on another file system, the file is copied instead.
+    pub(crate) fn write_atomic(&self, path: &Path, content: &[u8]) -> Result<()> {
+        let temp_path = self.get_temp_path();
+        if let Some(parent) = temp_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&temp_path, content)?;
 
-        fs::write(&path, content)?;
+        match fs::rename(&temp_path, path) {
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                fs::copy(&temp_path, path)?;
+                fs::remove_file(&temp_path)?;
+            }
+            result => result?,
+        }
         Ok(())
     }
 
+    /// Get the path of the file storing `section` in the profile `profile`
+    pub(crate) fn get_profile_path(&self, profile: &str, section: &str) -> PathBuf {
+        self.get_profiles_dir().join(profile).join(format!(
+            "{}.{}",
+            section,
+            self.format.extension()
+        ))
+    }
+
+    /// Get the directory containing one directory per profile
+    pub(crate) fn get_profiles_dir(&self) -> PathBuf {
+        self.base_path.join("profiles")
+    }
+
+    /// Encode a single value in the configured format, without any metadata
+    pub(crate) fn encode_value(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(match self.format {
+            SerializationFormat::Json => serde_json::to_vec_pretty(value)?,
+            #[cfg(feature = "yaml")]
+            SerializationFormat::Yaml => serde_norway::to_string(value)?.into_bytes(),
+            SerializationFormat::Toml => encode_toml(value.clone())?.into_bytes(),
+            SerializationFormat::Binary => {
+                bincode::encode_to_vec(serde_json::to_string(value)?, bincode::config::standard())?
+            }
+        })
+    }
+
+    /// Decode a single value written by [`Storage::encode_value`]
+    pub(crate) fn decode_value(&self, content: &[u8]) -> Result<Value> {
+        Ok(match self.format {
+            SerializationFormat::Json => serde_json::from_slice(content)?,
+            #[cfg(feature = "yaml")]
+            SerializationFormat::Yaml => decode_yaml(content)?,
+            SerializationFormat::Toml => decode_toml(content)?,
+            SerializationFormat::Binary => {
+                let (json, _): (String, usize) =
+                    bincode::decode_from_slice(content, bincode::config::standard())?;
+                serde_json::from_str(&json)?
+            }
+        })
+    }
+
     /// Delete the settings file
     ///
     /// This method is provided for manual control. When using the plugin system,
@@ -166,26 +728,364 @@ impl Storage {
     }
 }
 
+/// Encode a settings file in the binary format
+///
+/// The file starts with [`BINARY_MAGIC`] and the null-terminated file version, which is empty
+/// if there is none. It is followed by the settings as compact JSON encoded with bincode, since
+/// bincode can't decode self-describing formats like `serde_json::Value` directly.
+fn encode_binary(root: &Map<String, Value>, version: Option<&str>) -> Result<Vec<u8>> {
+    let mut content = BINARY_MAGIC.to_vec();
+    content.extend_from_slice(version.unwrap_or_default().as_bytes());
+    content.push(0);
+
+    let payload =
+        bincode::encode_to_vec(serde_json::to_string(root)?, bincode::config::standard())?;
+    content.extend_from_slice(&payload);
+    Ok(content)
+}
+
+/// Decode a settings file in the binary format, see [`encode_binary`]
+///
+/// The version from the header is returned as the `version` entry. Files without a header, as
+/// written by older releases, are decoded without a version.
+fn decode_binary(content: &[u8]) -> Result<Value> {
+    let (version, payload) = match content.strip_prefix(&BINARY_MAGIC) {
+        Some(rest) => {
+            let end =
+                rest.iter()
+                    .position(|&b| b == 0)
+                    .ok_or(bincode::error::DecodeError::Other(
+                        "unterminated version in settings header",
+                    ))?;
+            let version = String::from_utf8_lossy(&rest[..end]).into_owned();
+            (Some(version).filter(|v| !v.is_empty()), &rest[end + 1..])
+        }
+        None => (None, content),
+    };
+
+    let (json, _): (String, usize) =
+        bincode::decode_from_slice(payload, bincode::config::standard())?;
+    let mut root: Value = serde_json::from_str(&json)?;
+    if let (Some(version), Value::Object(map)) = (version, &mut root) {
+        map.insert("version".to_string(), Value::String(version));
+    }
+    Ok(root)
+}
+
+/// Decode a YAML settings file
+///
+/// Anchors and aliases are resolved by the parser, `<<` merge keys are applied before the
+/// document is converted, so hand-written files can share values between sections.
+#[cfg(feature = "yaml")]
+fn decode_yaml(content: &[u8]) -> Result<Value> {
+    let mut document: serde_norway::Value = serde_norway::from_slice(content)?;
+    document.apply_merge()?;
+    Ok(serde_json::to_value(document)?)
+}
+
+/// Encode a settings file as TOML
+///
+/// TOML has no null value, so `null` is written as a `{ _null = true }` table, which
+/// [`decode_toml`] turns back into `null`.
+fn encode_toml(mut value: Value) -> Result<String> {
+    replace_nulls(&mut value);
+    Ok(toml::to_string_pretty(&value)?)
+}
+
+/// Replace all `null` values with the [`TOML_NULL_KEY`] table, recursively
+fn replace_nulls(value: &mut Value) {
+    match value {
+        Value::Null => {
+            *value = Value::Object(Map::from_iter([(
+                TOML_NULL_KEY.to_string(),
+                Value::Bool(true),
+            )]))
+        }
+        Value::Object(map) => map.values_mut().for_each(replace_nulls),
+        Value::Array(array) => array.iter_mut().for_each(replace_nulls),
+        _ => {}
+    }
+}
+
+/// Decode a TOML settings file
+fn decode_toml(content: &[u8]) -> Result<Value> {
+    let mut value = toml::from_str(std::str::from_utf8(content)?)?;
+    restore_nulls(&mut value);
+    Ok(value)
+}
+
+/// Turn the tables written by [`replace_nulls`] back into `null`, recursively
+fn restore_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map)
+            if map.len() == 1 && map.get(TOML_NULL_KEY) == Some(&Value::Bool(true)) =>
+        {
+            *value = Value::Null
+        }
+        Value::Object(map) => map.values_mut().for_each(restore_nulls),
+        Value::Array(array) => array.iter_mut().for_each(restore_nulls),
+        _ => {}
+    }
+}
+
+/// Age after which a lock file is considered left behind, a write takes milliseconds
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// Lock file that is removed again when dropped
+///
+/// Stores the process id of its owner.
+struct FileLock(PathBuf);
+
+impl FileLock {
+    /// Create the lock file, failing with `AlreadyExists` if another instance holds it
+    ///
+    /// A stale lock, left behind by an instance that crashed while writing, is taken over.
+    fn acquire(path: PathBuf) -> std::io::Result<Self> {
+        match Self::create(path.clone()) {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && Self::is_stale(&path) => {
+                warn!("Removing stale settings lock file {}", path.display());
+                match fs::remove_file(&path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+                Self::create(path)
+            }
+            result => result,
+        }
+    }
+
+    fn create(path: PathBuf) -> std::io::Result<Self> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // Removes the lock file again if writing the process id fails
+        let lock = Self(path);
+        write!(file, "{}", std::process::id())?;
+        Ok(lock)
+    }
+
+    /// Whether the lock is older than [`STALE_LOCK_AGE`] or its owner is no longer running
+    fn is_stale(path: &Path) -> bool {
+        let age = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if age.is_some_and(|age| age >= STALE_LOCK_AGE) {
+            return true;
+        }
+
+        // An empty file is a lock that is still being created
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| content.trim().parse::<u32>().ok())
+            .is_some_and(|pid| !process_is_running(pid))
+    }
+}
+
+/// Whether a process with `pid` is running, assumed when it can't be checked
+fn process_is_running(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    pid == std::process::id()
+        || !cfg!(target_os = "linux")
+        || !proc.join("self").exists()
+        || proc.join(pid.to_string()).exists()
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Remove lines that start with `//` or `#` (ignoring indentation)
+///
+/// Comments after values on the same line are not supported.
+fn strip_comment_lines(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("//") && !line.starts_with('#')
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Detects modifications of the settings file, reporting at most one reload per debounce window
+///
+/// Editors often write a file in several steps; debouncing avoids reloading half-written files
+/// multiple times. Changes inside the window are not lost but reported once the window has
+/// passed.
+pub(crate) struct FileWatcher {
+    debounce: Duration,
+    /// Modification time seen on the last check, `None` until the first check
+    last_mtime: Option<Option<SystemTime>>,
+    last_reload: Option<Instant>,
+    pending: bool,
+}
+
+impl FileWatcher {
+    pub(crate) fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            last_mtime: None,
+            last_reload: None,
+            pending: false,
+        }
+    }
+
+    /// Check whether the file at `path` changed and should be reloaded now
+    ///
+    /// The first call only records the current state of the file.
+    pub(crate) fn should_reload(&mut self, path: &Path) -> bool {
+        let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        match self.last_mtime {
+            None => {
+                self.last_mtime = Some(mtime);
+                return false;
+            }
+            Some(last) if last != mtime => {
+                self.last_mtime = Some(mtime);
+                self.pending = true;
+            }
+            _ => {}
+        }
+
+        let window_passed = self
+            .last_reload
+            .is_none_or(|last_reload| last_reload.elapsed() >= self.debounce);
+
+        if self.pending && window_passed {
+            self.pending = false;
+            self.last_reload = Some(Instant::now());
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Compute delta between current settings and defaults
 /// Returns None if settings equal defaults, otherwise returns a Value with only changed fields
+#[allow(dead_code)]
 pub(crate) fn compute_delta<T: Settings>(settings: &T) -> Option<Value> {
-    let defaults = T::default();
+    compute_delta_from_base(settings, &T::default())
+}
 
-    // If equal to defaults, no need to store
-    if settings == &defaults {
+/// Compute delta between current settings and an explicit base value
+pub(crate) fn compute_delta_from_base<T: Settings>(settings: &T, base: &T) -> Option<Value> {
+    // If equal to the base, no need to store
+    let forced = T::always_persist_fields();
+    if settings == base && forced.is_empty() {
         return None;
     }
 
     // Serialize both to JSON values
     let settings_value = serde_json::to_value(settings).ok()?;
-    let defaults_value = serde_json::to_value(&defaults).ok()?;
+    let defaults_value = serde_json::to_value(base).ok()?;
 
     // Compute delta recursively
-    compute_value_delta(&settings_value, &defaults_value)
+    let mut delta = match compute_value_delta(&settings_value, &defaults_value) {
+        Some(delta) => delta,
+        None if !forced.is_empty() && settings_value.is_object() => Value::Object(Map::new()),
+        None => return None,
+    };
+
+    // Sets are stored as the elements added to and removed from the default set
+    if let Value::Object(delta_map) = &mut delta {
+        for field in T::set_fields() {
+            let (Some(Value::Array(current)), Some(Value::Array(default))) =
+                (settings_value.get(field), defaults_value.get(field))
+            else {
+                continue;
+            };
+            let added: Vec<Value> = current
+                .iter()
+                .filter(|value| !default.contains(value))
+                .cloned()
+                .collect();
+            let removed: Vec<Value> = default
+                .iter()
+                .filter(|value| !current.contains(value))
+                .cloned()
+                .collect();
+
+            if added.is_empty() && removed.is_empty() {
+                // Only the iteration order differs
+                delta_map.remove(*field);
+            } else {
+                let mut patch = Map::new();
+                patch.insert("added".to_string(), Value::Array(added));
+                patch.insert("removed".to_string(), Value::Array(removed));
+                delta_map.insert(field.to_string(), Value::Object(patch));
+            }
+        }
+
+        // Fields that are always persisted are stored even if they equal the base
+        for field in forced {
+            if let Some(value) = settings_value.get(field) {
+                delta_map
+                    .entry(field.to_string())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+
+        if delta_map.is_empty() {
+            return None;
+        }
+    }
+
+    Some(delta)
+}
+
+/// Rename the keys of a delta that use a serde alias to the current field name
+///
+/// If both keys are present, the old one is dropped. Returns whether the delta was changed, see
+/// [`Settings::serde_aliases`].
+pub(crate) fn apply_serde_aliases<T: Settings>(delta: &mut Value) -> bool {
+    let Value::Object(delta_map) = delta else {
+        return false;
+    };
+    let mut changed = false;
+    for (alias, field) in T::serde_aliases() {
+        let Some(value) = delta_map.remove(*alias) else {
+            continue;
+        };
+        delta_map.entry(*field).or_insert(value);
+        changed = true;
+    }
+    changed
+}
+
+/// Replacement for the values of secret fields in log output
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Replace the values of the top-level secret fields of a delta for logging, see
+/// [`Settings::secret_fields`]
+pub(crate) fn redact<T: Settings>(delta: &Value) -> Value {
+    let mut redacted = delta.clone();
+    if let Value::Object(map) = &mut redacted {
+        for field in T::secret_fields() {
+            if let Some(value) = map.get_mut(*field) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    redacted
+}
+
+/// Check whether an object is the delta of a set field, see [`Settings::set_fields`]
+fn is_set_patch(patch: &Map<String, Value>) -> bool {
+    !patch.is_empty()
+        && patch
+            .iter()
+            .all(|(key, value)| (key == "added" || key == "removed") && value.is_array())
 }
 
 /// Recursively compute delta between two JSON values
-fn compute_value_delta(current: &Value, default: &Value) -> Option<Value> {
+pub(crate) fn compute_value_delta(current: &Value, default: &Value) -> Option<Value> {
     match (current, default) {
         (Value::Object(curr_map), Value::Object(def_map)) => {
             let mut delta_map = Map::new();
@@ -222,40 +1122,188 @@ fn compute_value_delta(current: &Value, default: &Value) -> Option<Value> {
     }
 }
 
+/// Options controlling how a delta is merged into the defaults
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MergeOptions {
+    /// Merge objects inside arrays element by element instead of replacing the whole array
+    pub arrays_by_index: bool,
+    /// How floats are decoded from the merged JSON
+    pub json: JsonOptions,
+}
+
 /// Merge delta with defaults to get complete settings
 pub(crate) fn merge_with_defaults<T: Settings>(delta: Option<&Value>) -> Result<T> {
-    let defaults = T::default();
+    merge_with_base(T::default(), delta, MergeOptions::default())
+}
 
-    // If no delta, return defaults
+/// Merge delta with an explicit base value to get complete settings
+pub(crate) fn merge_with_base<T: Settings>(
+    base: T,
+    delta: Option<&Value>,
+    options: MergeOptions,
+) -> Result<T> {
+    // If no delta, return the base
     let Some(delta) = delta else {
-        return Ok(defaults);
+        return Ok(base);
     };
 
-    // Serialize defaults to JSON
-    let mut defaults_value = serde_json::to_value(&defaults)?;
+    // Serialize base to JSON
+    let mut defaults_value = serde_json::to_value(&base)?;
 
     // Merge delta into defaults
-    merge_values(&mut defaults_value, delta);
+    merge_values(&mut defaults_value, delta, options);
 
     // Deserialize back to T
-    let result: T = serde_json::from_value(defaults_value)?;
-    Ok(result)
+    match from_value_with_options(defaults_value, options.json) {
+        Ok(result) => Ok(result),
+        // The delta of an enum whose variant differs from the default is the whole value, which
+        // can't be merged into the other variant
+        Err(e) => from_value_with_options(delta.clone(), options.json).map_err(|_| e.into()),
+    }
+}
+
+/// Remove the fields of a delta that don't fit the settings type
+///
+/// Each top-level field is checked on its own: fields that don't exist in `base` or that can't
+/// be deserialized (wrong type, out of range, ...) are dropped with a warning, so they fall back
+/// to their default values while the valid fields are kept.
+pub(crate) fn validate_delta<T: Settings>(
+    base: &T,
+    delta: &Value,
+    options: MergeOptions,
+    observer: Option<&dyn SettingsObserver>,
+) -> Value {
+    let Value::Object(delta_map) = delta else {
+        return delta.clone();
+    };
+    let Ok(Value::Object(base_map)) = serde_json::to_value(base) else {
+        return delta.clone();
+    };
+
+    let mut valid = Map::new();
+    for (key, value) in delta_map {
+        if !base_map.contains_key(key) {
+            warn!(
+                "Ignoring unknown field '{}' in settings for {}",
+                key,
+                T::type_name()
+            );
+            if let Some(observer) = observer {
+                observer.on_event(SettingsObservabilityEvent::ValidationFailed {
+                    section: get_type_key::<T>(),
+                    field: Some(key.clone()),
+                    error: "unknown field".to_string(),
+                });
+            }
+            continue;
+        }
+
+        let mut field = Map::new();
+        field.insert(key.clone(), value.clone());
+        if let Err(e) = merge_with_base(base.clone(), Some(&Value::Object(field)), options) {
+            // Deserialization errors can quote the value
+            let (value, e) = if T::secret_fields().contains(&key.as_str()) {
+                (Value::String(REDACTED.to_string()), REDACTED.to_string())
+            } else {
+                (value.clone(), e.to_string())
+            };
+            warn!(
+                "Invalid value {} for field '{}' in settings for {}: {}. Using default.",
+                value,
+                key,
+                T::type_name(),
+                e
+            );
+            if let Some(observer) = observer {
+                observer.on_event(SettingsObservabilityEvent::ValidationFailed {
+                    section: get_type_key::<T>(),
+                    field: Some(key.clone()),
+                    error: e,
+                });
+            }
+            continue;
+        }
+
+        valid.insert(key.clone(), value.clone());
+    }
+
+    Value::Object(valid)
+}
+
+/// Check a delta against a JSON Schema, returning the path and description of every violation
+pub(crate) fn check_schema(
+    schema: &Value,
+    delta: &Value,
+) -> std::result::Result<(), Vec<(String, String)>> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| vec![(String::new(), format!("invalid schema: {}", e))])?;
+
+    let errors: Vec<(String, String)> = validator
+        .iter_errors(delta)
+        .map(|e| (e.instance_path.to_string(), e.to_string()))
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// SHA-256 hash of a section serialized as compact JSON
+fn content_hash(value: &Value) -> String {
+    Sha256::digest(serde_json::to_vec(value).unwrap_or_default())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Size of a value serialized as compact JSON
+pub(crate) fn json_size(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |json| json.len())
 }
 
 /// Recursively merge source into target
-fn merge_values(target: &mut Value, source: &Value) {
+///
+/// When merging arrays by index, the result has the length of the source array. Objects at the
+/// same index are merged recursively, so an empty object keeps the target element unchanged.
+pub(crate) fn merge_values(target: &mut Value, source: &Value, options: MergeOptions) {
     match (target, source) {
         (Value::Object(target_map), Value::Object(source_map)) => {
             for (key, source_val) in source_map {
                 if let Some(target_val) = target_map.get_mut(key) {
                     // Recursively merge nested objects
-                    merge_values(target_val, source_val);
+                    merge_values(target_val, source_val, options);
                 } else {
                     // Key doesn't exist in target, add it
                     target_map.insert(key.clone(), source_val.clone());
                 }
             }
         }
+        (Value::Array(target_vec), Value::Object(patch)) if is_set_patch(patch) => {
+            if let Some(Value::Array(removed)) = patch.get("removed") {
+                target_vec.retain(|value| !removed.contains(value));
+            }
+            if let Some(Value::Array(added)) = patch.get("added") {
+                for value in added {
+                    if !target_vec.contains(value) {
+                        target_vec.push(value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(target_vec), Value::Array(source_vec)) if options.arrays_by_index => {
+            target_vec.truncate(source_vec.len());
+            for (index, source_val) in source_vec.iter().enumerate() {
+                match target_vec.get_mut(index) {
+                    Some(target_val) if target_val.is_object() && source_val.is_object() => {
+                        merge_values(target_val, source_val, options);
+                    }
+                    Some(target_val) => *target_val = source_val.clone(),
+                    None => target_vec.push(source_val.clone()),
+                }
+            }
+        }
         (target, source) => {
             // Replace target with source
             *target = source.clone();
@@ -264,40 +1312,385 @@ fn merge_values(target: &mut Value, source: &Value) {
 }
 
 /// System that saves a specific settings type to the storage
+///
+/// Changes rejected by a validator are reverted to the stored settings instead of being saved.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn save_settings_on_change<T: Settings>(
-    settings: Res<T>,
+    mut settings: ResMut<T>,
+    config: Res<SettingsTypeConfig<T>>,
     manager: Res<SettingsManager>,
+    mut pending: ResMut<PendingSave>,
+    mut stats: ResMut<StorageStats>,
+    history: Option<ResMut<SettingsHistory<T>>>,
+    validators: Option<Res<SettingsValidators<T>>>,
+    mut validation_failed: MessageWriter<SettingsValidationFailed>,
+    mut changed: MessageWriter<SettingsChanged<T>>,
+    hot_reload: Option<Res<HotReload>>,
 ) {
-    if settings.is_changed() && !settings.is_added() {
-        let type_key = get_type_key::<T>();
+    if let (true, false, Some(validators)) =
+        (settings.is_changed(), settings.is_added(), validators)
+    {
+        if let Err(reason) = validators.validate(&settings) {
+            warn!(
+                "Invalid settings for {}: {}. Reverting the change.",
+                T::type_name(),
+                reason
+            );
+            *settings = manager.stored_settings((config.default_fn)());
+            validation_failed.write(SettingsValidationFailed {
+                type_name: T::type_name(),
+                reason,
+            });
+            return;
+        }
+    }
 
-        // Compute delta (only changed fields)
-        let delta = crate::storage::compute_delta(&*settings);
+    if let Some(mut history) = history {
+        if settings.is_added() {
+            history.reset(&settings);
+        } else if settings.is_changed() {
+            history.record(&settings);
+        }
+    }
 
-        // Update the shared settings map
-        let mut map = manager.settings_map.lock().unwrap();
+    if settings.is_changed() && !settings.is_added() {
+        // Compute delta (only changed fields) and update the shared settings map
+        manager.update_delta::<T>(&settings, &(config.default_fn)());
 
-        if let Some(delta_value) = delta {
-            map.insert(type_key.clone(), delta_value);
-        } else {
-            // Settings equal defaults, remove from map
-            map.remove(&type_key);
+        // Hot reloads are applied right before this system, in the same frame
+        let source = match hot_reload {
+            Some(reload) if reload.settings.is_some() => SettingsChangeSource::HotReload,
+            _ => SettingsChangeSource::Command,
+        };
+
+        // With a write delay or debounce, the save is flushed later by `flush_pending_save`
+        if manager.write_delay.is_some() || manager.save_debounce.is_some() {
+            let now = Instant::now();
+            pending.requested_at.get_or_insert(now);
+            pending.changed_at = Some(now);
+            // Only the latest change of each type is reported once the write succeeds
+            let value = settings.clone();
+            pending.changes.insert(
+                TypeId::of::<T>(),
+                Box::new(move |world: &mut World| {
+                    world.write_message(SettingsChanged { value, source });
+                }),
+            );
+            return;
         }
 
         // Save all settings to disk
-        if let Err(e) = manager.storage.save_all(&map) {
+        if let Err(e) = manager.save(&mut stats) {
             error!("Failed to save settings: {}", e);
         } else {
             info!("Settings saved");
+            changed.write(SettingsChanged {
+                value: settings.clone(),
+                source,
+            });
         }
     }
 }
+
+/// Sends the [`SettingsChanged`] message of a pending change
+type PendingMessage = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/// Tracks a save that is waiting for the write delay or debounce to pass
+#[derive(Resource, Default)]
+pub(crate) struct PendingSave {
+    /// Time of the first change since the last write
+    pub requested_at: Option<Instant>,
+    /// Time of the latest change since the last write
+    pub changed_at: Option<Instant>,
+    /// Messages of the pending changes by settings type, sent once the write succeeded
+    changes: HashMap<TypeId, PendingMessage>,
+}
+
+/// System that writes pending changes once the write delay or debounce has passed, or when the
+/// app exits
+///
+/// With both configured, the write delay is the longest a change waits while changes keep
+/// coming in.
+pub(crate) fn flush_pending_save(
+    manager: Res<SettingsManager>,
+    mut pending: ResMut<PendingSave>,
+    mut stats: ResMut<StorageStats>,
+    mut exit: MessageReader<AppExit>,
+    mut commands: Commands,
+) {
+    let exiting = exit.read().count() > 0;
+    let Some(requested_at) = pending.requested_at else {
+        return;
+    };
+
+    let delay_passed = manager
+        .write_delay
+        .is_some_and(|delay| requested_at.elapsed() >= delay);
+    let changes_stopped = manager.save_debounce.is_some_and(|debounce| {
+        pending
+            .changed_at
+            .is_none_or(|changed_at| changed_at.elapsed() >= debounce)
+    });
+    if !exiting && !delay_passed && !changes_stopped {
+        return;
+    }
+
+    for message in write_pending_save(&manager, &mut pending, &mut stats) {
+        commands.queue(message);
+    }
+}
+
+/// Write pending changes to disk right away, ignoring the write delay and debounce
+///
+/// Meant for shutdown hooks and other places where changes must not wait, e.g. before the
+/// process is terminated without a regular [`AppExit`]. Can be added as a system or called
+/// with a [`World`] directly.
+pub fn flush_settings_now(world: &mut World) {
+    let messages = world.resource_scope(|world, mut pending: Mut<PendingSave>| {
+        world.resource_scope(|world, mut stats: Mut<StorageStats>| {
+            let manager = world.resource::<SettingsManager>();
+            write_pending_save(manager, &mut pending, &mut stats)
+        })
+    });
+    for message in messages {
+        message(world);
+    }
+}
+
+/// Write pending changes to disk and clear the pending save
+///
+/// Returns the [`SettingsChanged`] messages to send, which are dropped when the write fails.
+fn write_pending_save(
+    manager: &SettingsManager,
+    pending: &mut PendingSave,
+    stats: &mut StorageStats,
+) -> Vec<PendingMessage> {
+    if pending.requested_at.take().is_none() {
+        return Vec::new();
+    }
+    pending.changed_at = None;
+    let messages = std::mem::take(&mut pending.changes);
+
+    if let Err(e) = manager.save(stats) {
+        error!("Failed to save settings: {}", e);
+        Vec::new()
+    } else {
+        info!("Settings saved");
+        messages.into_values().collect()
+    }
+}
+
+/// Per-type configuration shared by the load and save systems of a settings type
+#[derive(Resource)]
+pub(crate) struct SettingsTypeConfig<T: Settings> {
+    /// Produces the values deltas are computed against
+    pub default_fn: fn() -> T,
+    /// Schema version of this settings type, stored in the `_versions` section
+    pub version: Option<semver::Version>,
+    /// Upgrades deltas that were saved with an older schema version
+    pub migrate: Option<MigrationFn>,
+}
+
+impl<T: Settings> Clone for SettingsTypeConfig<T> {
+    fn clone(&self) -> Self {
+        Self {
+            default_fn: self.default_fn,
+            version: self.version.clone(),
+            migrate: self.migrate,
+        }
+    }
+}
+
 #[derive(Resource, Clone)]
 pub(crate) struct SettingsManager {
     pub storage: Storage,
     /// Shared map of all settings values (type_key -> JSON value)
     /// Using Arc<Mutex<>> to allow multiple systems to update the same map
     pub settings_map: Arc<Mutex<HashMap<String, Value>>>,
+    /// Schema versions of the settings types as stored in the file (type_key -> version)
+    pub versions: Arc<Mutex<HashMap<String, String>>>,
+    /// Schema versions the settings types were registered with (type_key -> version)
+    pub target_versions: Arc<Mutex<HashMap<String, semver::Version>>>,
+    /// What to do when a migration fails
+    pub migration_error_policy: MigrationErrorPolicy,
+    /// What to do when a section was saved by a newer schema version
+    pub downgrade_policy: DowngradePolicy,
+    /// Delay between a change and writing it to disk, `None` to write immediately
+    pub write_delay: Option<Duration>,
+    /// Time without further changes before writing them to disk, `None` to not wait for
+    /// changes to stop
+    pub save_debounce: Option<Duration>,
+    /// Number of values kept for undo per settings type, `None` to keep no history
+    pub history_depth: Option<usize>,
+    /// Drop invalid fields from loaded deltas instead of rejecting the whole section
+    pub schema_validation: bool,
+    /// Run migrations without ever writing the results to disk
+    pub migration_dry_run: bool,
+    /// Field values set outside the settings file, by section
+    pub overrides: HashMap<String, Vec<FieldOverride>>,
+    /// Where the overrides of a section came from, the arguments if both were used
+    pub override_sources: HashMap<String, SettingsChangeSource>,
+    /// Sections saved by a newer schema version that are loaded as defaults, kept in the file
+    /// as they are
+    pub protected_sections: Arc<Mutex<HashSet<String>>>,
+}
+
+impl SettingsManager {
+    /// Create a manager whose maps are pre-allocated for `capacity` settings types
+    pub(crate) fn new(storage: Storage, capacity: usize) -> Self {
+        Self {
+            storage,
+            settings_map: Arc::new(Mutex::new(HashMap::with_capacity(capacity))),
+            versions: Arc::new(Mutex::new(HashMap::with_capacity(capacity))),
+            target_versions: Arc::new(Mutex::new(HashMap::with_capacity(capacity))),
+            migration_error_policy: MigrationErrorPolicy::default(),
+            downgrade_policy: DowngradePolicy::default(),
+            write_delay: None,
+            save_debounce: None,
+            history_depth: None,
+            schema_validation: false,
+            migration_dry_run: false,
+            overrides: HashMap::new(),
+            override_sources: HashMap::new(),
+            protected_sections: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Fill in sections and versions from a loaded file that aren't tracked yet
+    ///
+    /// This keeps sections of types that haven't been loaded (or registered) in the file
+    /// when other types are saved.
+    pub(crate) fn seed(&self, all_settings: &Map<String, Value>) {
+        let mut map = self.settings_map.lock().unwrap();
+        let mut versions = self.versions.lock().unwrap();
+
+        for (key, value) in all_settings {
+            if key == VERSIONS_KEY {
+                let Value::Object(stored) = value else {
+                    continue;
+                };
+                for (key, version) in stored {
+                    if let Some(version) = version.as_str() {
+                        versions
+                            .entry(key.clone())
+                            .or_insert_with(|| version.to_string());
+                    }
+                }
+            } else {
+                map.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    /// Store the delta between `settings` and `base` in the shared settings map
+    ///
+    /// Overridden fields that still hold their override are stored with their previous value.
+    /// Protected sections keep the delta they were loaded with.
+    pub(crate) fn update_delta<T: Settings>(&self, settings: &T, base: &T) {
+        let type_key = get_type_key::<T>();
+        if self.protected_sections.lock().unwrap().contains(&type_key) {
+            return;
+        }
+        let mut map = self.settings_map.lock().unwrap();
+
+        let reverted;
+        let settings = match self.overrides.get(&type_key) {
+            Some(overrides) => {
+                reverted = self.revert_overrides(settings, base, map.get(&type_key), overrides);
+                &reverted
+            }
+            None => settings,
+        };
+
+        if let Some(delta_value) = compute_delta_from_base(settings, base) {
+            map.insert(type_key, delta_value);
+        } else {
+            // Settings equal defaults, remove from map
+            map.remove(&type_key);
+        }
+    }
+
+    /// Get where loaded settings of the type come from
+    pub(crate) fn load_source<T: Settings>(&self) -> SettingsChangeSource {
+        self.override_sources
+            .get(&get_type_key::<T>())
+            .copied()
+            .unwrap_or(SettingsChangeSource::File)
+    }
+
+    /// Get the settings as stored in the shared settings map, with the overrides applied
+    ///
+    /// This is the value of the last accepted change, which may not be written to disk yet.
+    pub(crate) fn stored_settings<T: Settings>(&self, base: T) -> T {
+        let delta = self
+            .settings_map
+            .lock()
+            .unwrap()
+            .get(&get_type_key::<T>())
+            .cloned();
+        let settings = merge_with_base(base.clone(), delta.as_ref(), self.storage.merge_options)
+            .unwrap_or(base);
+        self.apply_overrides(settings)
+    }
+
+    /// Apply the overrides of the settings type on top of the loaded settings
+    pub(crate) fn apply_overrides<T: Settings>(&self, settings: T) -> T {
+        let Some(overrides) = self.overrides.get(&get_type_key::<T>()) else {
+            return settings;
+        };
+        let Ok(mut value) = serde_json::to_value(&settings) else {
+            return settings;
+        };
+
+        apply_overrides(&mut value, overrides);
+        match from_value_with_options(value, self.storage.merge_options.json) {
+            Ok(overridden) => overridden,
+            Err(e) => {
+                warn!(
+                    "Invalid override for settings of {}: {}. Ignoring overrides.",
+                    T::type_name(),
+                    e
+                );
+                settings
+            }
+        }
+    }
+
+    /// Get `settings` with the overridden fields reset to their persisted values
+    fn revert_overrides<T: Settings>(
+        &self,
+        settings: &T,
+        base: &T,
+        delta: Option<&Value>,
+        overrides: &[FieldOverride],
+    ) -> T {
+        let (Ok(mut value), Ok(mut persisted)) =
+            (serde_json::to_value(settings), serde_json::to_value(base))
+        else {
+            return settings.clone();
+        };
+        if let Some(delta) = delta {
+            merge_values(&mut persisted, delta, self.storage.merge_options);
+        }
+
+        revert_overrides(&mut value, &persisted, overrides);
+        from_value_with_options(value, self.storage.merge_options.json)
+            .unwrap_or_else(|_| settings.clone())
+    }
+
+    /// Write all tracked settings and versions to disk
+    ///
+    /// Does nothing during a migration dry run.
+    pub(crate) fn save(&self, stats: &mut StorageStats) -> Result<()> {
+        if self.migration_dry_run {
+            info!("Migration dry run, not writing settings to disk");
+            return Ok(());
+        }
+
+        let map = self.settings_map.lock().unwrap();
+        let versions = self.versions.lock().unwrap();
+        self.storage.save_all(&map, &versions, stats)
+    }
 }
 
 /// Get the type key for a settings type (lowercase type name)
@@ -305,6 +1698,18 @@ pub(crate) fn get_type_key<T: Settings>() -> String {
     T::type_name().to_lowercase()
 }
 
+/// Check whether a type key can be used as a section name in the settings file
+///
+/// Dots and slashes would make paths into the file ambiguous, and a leading `_` is reserved for
+/// metadata sections like `_versions`.
+pub(crate) fn is_valid_type_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('_')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -339,8 +1744,10 @@ mod tests {
 
     #[test]
     fn test_compute_delta_with_changes() {
-        let mut settings = TestSettings::default();
-        settings.value = 42;
+        let settings = TestSettings {
+            value: 42,
+            ..Default::default()
+        };
 
         let delta = compute_delta(&settings);
         assert!(delta.is_some());
@@ -353,6 +1760,327 @@ mod tests {
         assert!(delta_value.get("name").is_none());
     }
 
+    #[test]
+    fn test_storage_stats_count_saves_and_loads() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_storage_stats");
+        let _ = fs::remove_dir_all(&base_path);
+
+        let storage = Storage::new("Stats", SerializationFormat::Json).with_base_path(&base_path);
+        let mut stats = StorageStats::default();
+
+        // Reading a missing file doesn't count as a load
+        storage.load_all(&mut stats).unwrap();
+        assert_eq!(stats.loads, 0);
+        assert!(stats.last_load.is_none());
+
+        let mut settings_map = HashMap::new();
+        for value in 1..=3 {
+            settings_map.insert("stats".to_string(), Value::Number(value.into()));
+            storage
+                .save_all(&settings_map, &HashMap::new(), &mut stats)
+                .unwrap();
+            storage.load_all(&mut stats).unwrap();
+        }
+
+        assert_eq!(stats.saves, 3);
+        assert_eq!(stats.loads, 3);
+        assert!(stats.bytes_written > 0);
+        assert_eq!(stats.bytes_written, stats.bytes_read);
+        assert!(stats.last_save.is_some());
+        assert!(stats.last_load.unwrap() >= stats.last_save.unwrap());
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_binary_header() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_binary_header");
+        let _ = fs::remove_dir_all(&base_path);
+
+        let storage = Storage::new("Binary", SerializationFormat::Binary)
+            .with_base_path(&base_path)
+            .with_version("1.0.0");
+        let mut stats = StorageStats::default();
+
+        let mut settings_map = HashMap::new();
+        settings_map.insert("binary".to_string(), serde_json::json!({ "value": 7 }));
+        storage
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+
+        let content = fs::read(storage.get_path()).unwrap();
+        assert!(content.starts_with(b"BSET1.0.0\0"));
+
+        let root = decode_binary(&content).unwrap();
+        assert_eq!(root["version"], "1.0.0");
+        assert_eq!(root["binary"], serde_json::json!({ "value": 7 }));
+
+        let loaded = storage.load_all(&mut stats).unwrap();
+        assert_eq!(loaded.get("binary"), settings_map.get("binary"));
+        assert!(!loaded.contains_key("version"));
+
+        // Files without a header are decoded without a version
+        let headerless = &content[BINARY_MAGIC.len() + "1.0.0".len() + 1..];
+        let root = decode_binary(headerless).unwrap();
+        assert!(root.get("version").is_none());
+        assert_eq!(root["binary"], serde_json::json!({ "value": 7 }));
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_temp_dir() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_temp_dir");
+        let temp_dir = base_path.join("tmp");
+        let _ = fs::remove_dir_all(&base_path);
+
+        let mut storage = Storage::new("Atomic", SerializationFormat::Json)
+            .with_base_path(base_path.join("data"));
+        assert_eq!(
+            storage.get_temp_path().parent(),
+            Some(storage.base_path.as_path())
+        );
+
+        storage.temp_dir = Some(temp_dir.clone());
+        let temp_path = storage.get_temp_path();
+        assert_eq!(temp_path.parent(), Some(temp_dir.as_path()));
+        let temp_name = temp_path.file_name().unwrap().to_str().unwrap();
+        assert!(temp_name.starts_with("Atomic.json.") && temp_name.ends_with(".tmp"));
+        assert_ne!(storage.get_temp_path(), temp_path);
+
+        let mut stats = StorageStats::default();
+        let mut settings_map = HashMap::new();
+        settings_map.insert("atomic".to_string(), Value::from(1));
+        storage
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+
+        // The temporary file was created in the configured directory and moved into place
+        assert!(temp_dir.is_dir());
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(&storage.base_path).unwrap().count(), 1);
+        assert!(storage.load_all(&mut stats).unwrap().contains_key("atomic"));
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_lock_file() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_lock_file");
+        let _ = fs::remove_dir_all(&base_path);
+
+        let mut first =
+            Storage::new("Locked", SerializationFormat::Json).with_base_path(&base_path);
+        first.lock_file = true;
+        let second = first.clone();
+        let mut stats = StorageStats::default();
+
+        let mut settings_map = HashMap::new();
+        settings_map.insert("locked".to_string(), Value::from(1));
+
+        // The first instance holds the lock while the second one tries to save
+        fs::create_dir_all(&base_path).unwrap();
+        let lock = FileLock::acquire(first.get_lock_path()).unwrap();
+        second
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+        assert_eq!(stats.saves, 0);
+        assert!(!first.get_path().exists());
+        drop(lock);
+
+        first
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+        assert_eq!(stats.saves, 1);
+        assert!(first.get_path().exists());
+        assert!(!first.get_lock_path().exists());
+
+        // Resetting everything to the defaults deletes the file, which also needs the lock
+        let lock = FileLock::acquire(first.get_lock_path()).unwrap();
+        second
+            .save_all(&HashMap::new(), &HashMap::new(), &mut stats)
+            .unwrap();
+        assert!(first.get_path().exists());
+        drop(lock);
+        second
+            .save_all(&HashMap::new(), &HashMap::new(), &mut stats)
+            .unwrap();
+        assert!(!first.get_path().exists());
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_stale_lock_file_is_taken_over() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_stale_lock_file");
+        let _ = fs::remove_dir_all(&base_path);
+        fs::create_dir_all(&base_path).unwrap();
+        let lock_path = base_path.join("Stale.lock");
+
+        // A lock of a running process is respected
+        fs::write(&lock_path, std::process::id().to_string()).unwrap();
+        assert_eq!(
+            FileLock::acquire(lock_path.clone()).err().map(|e| e.kind()),
+            Some(ErrorKind::AlreadyExists)
+        );
+
+        // Until it is older than a write could take
+        fs::File::options()
+            .write(true)
+            .open(&lock_path)
+            .unwrap()
+            .set_modified(SystemTime::now() - STALE_LOCK_AGE)
+            .unwrap();
+        drop(FileLock::acquire(lock_path.clone()).unwrap());
+        assert!(!lock_path.exists());
+
+        // A lock of a process that is no longer running is free right away, where processes
+        // can be checked
+        if cfg!(target_os = "linux") {
+            let mut child = std::process::Command::new("true").spawn().unwrap();
+            child.wait().unwrap();
+            fs::write(&lock_path, child.id().to_string()).unwrap();
+            let lock = FileLock::acquire(lock_path.clone()).unwrap();
+            assert_eq!(
+                fs::read_to_string(&lock_path).unwrap(),
+                std::process::id().to_string()
+            );
+            drop(lock);
+        }
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        let base_path = std::env::temp_dir().join("bevy_settings_test_max_file_size");
+        let _ = fs::remove_dir_all(&base_path);
+
+        let mut storage =
+            Storage::new("Limited", SerializationFormat::Json).with_base_path(&base_path);
+        let mut stats = StorageStats::default();
+
+        let mut settings_map = HashMap::new();
+        settings_map.insert("limited".to_string(), Value::String("x".repeat(100)));
+        storage
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+
+        storage.max_file_size = Some(64);
+        let result = storage.load_all(&mut stats);
+        assert!(matches!(
+            result,
+            Err(crate::error::SettingsError::FileTooLarge { limit: 64, .. })
+        ));
+        assert_eq!(stats.loads, 0);
+
+        storage.max_file_size = Some(1024);
+        assert!(storage
+            .load_all(&mut stats)
+            .unwrap()
+            .contains_key("limited"));
+
+        // The limit applies to the decompressed size of compressed files as well
+        settings_map.insert("limited".to_string(), Value::String("x".repeat(4096)));
+        storage.compression_threshold = Some(256);
+        storage
+            .save_all(&settings_map, &HashMap::new(), &mut stats)
+            .unwrap();
+        assert!(fs::metadata(storage.get_path()).unwrap().len() < 1024);
+        assert!(matches!(
+            storage.load_all(&mut stats),
+            Err(crate::error::SettingsError::FileTooLarge { limit: 1024, .. })
+        ));
+
+        let _ = fs::remove_dir_all(&base_path);
+    }
+
+    #[test]
+    fn test_file_watcher_debounce() {
+        let dir = std::env::temp_dir().join("bevy_settings_test_file_watcher");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Watched.json");
+        fs::write(&path, "{}").unwrap();
+
+        let debounce = Duration::from_millis(200);
+        let mut watcher = FileWatcher::new(debounce);
+
+        // The first check only records the current state
+        assert!(!watcher.should_reload(&path));
+        assert!(!watcher.should_reload(&path));
+
+        std::thread::sleep(Duration::from_millis(10));
+        fs::write(&path, r#"{ "a": 1 }"#).unwrap();
+        assert!(watcher.should_reload(&path));
+        assert!(!watcher.should_reload(&path));
+
+        // A second change inside the debounce window is held back...
+        std::thread::sleep(Duration::from_millis(10));
+        fs::write(&path, r#"{ "a": 2 }"#).unwrap();
+        assert!(!watcher.should_reload(&path));
+
+        // ...and reported once the window has passed
+        std::thread::sleep(debounce);
+        assert!(watcher.should_reload(&path));
+        assert!(!watcher.should_reload(&path));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_delta_drops_invalid_fields() {
+        let delta = serde_json::json!({
+            "value": "not a number",
+            "name": "custom",
+            "unknown": true,
+        });
+
+        let valid = validate_delta(
+            &TestSettings::default(),
+            &delta,
+            MergeOptions::default(),
+            None,
+        );
+        assert_eq!(valid, serde_json::json!({ "name": "custom" }));
+    }
+
+    #[test]
+    fn test_content_hash_is_sha256_hex() {
+        // SHA-256 of `"abc"`, including the quotes of the JSON string
+        assert_eq!(
+            content_hash(&serde_json::json!("abc")),
+            "6cc43f858fbb763301637b5af970e2a46b46f461f27e5a0f41e009c59b827b25"
+        );
+    }
+
+    #[test]
+    fn test_merge_arrays_by_index() {
+        let mut target = serde_json::json!([
+            { "action": "jump", "key": "Space" },
+            { "action": "crouch", "key": "C" },
+        ]);
+        let source = serde_json::json!([{ "key": "W" }, {}, { "action": "run", "key": "R" }]);
+
+        let mut replaced = target.clone();
+        merge_values(&mut replaced, &source, MergeOptions::default());
+        assert_eq!(replaced, source);
+
+        let options = MergeOptions {
+            arrays_by_index: true,
+            ..Default::default()
+        };
+        merge_values(&mut target, &source, options);
+        assert_eq!(
+            target,
+            serde_json::json!([
+                { "action": "jump", "key": "W" },
+                { "action": "crouch", "key": "C" },
+                { "action": "run", "key": "R" },
+            ])
+        );
+    }
+
     #[test]
     fn test_merge_with_defaults() {
         let mut delta_map = Map::new();
@@ -363,4 +2091,28 @@ mod tests {
         assert_eq!(result.value, 100);
         assert_eq!(result.name, String::default()); // Should use default
     }
+
+    #[test]
+    fn test_toml_round_trips_null() {
+        let value = serde_json::json!({
+            "version": "1.0.0",
+            "section": {
+                "limit": null,
+                "levels": [1, null],
+                "pair": [null, [null]],
+                "nested": { "a": null, "b": 1 },
+            },
+        });
+
+        let encoded = encode_toml(value.clone()).unwrap();
+        assert_eq!(decode_toml(encoded.as_bytes()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_toml_rejects_invalid_utf8() {
+        assert!(matches!(
+            decode_toml(b"name = \"\xff\""),
+            Err(crate::error::SettingsError::Utf8(_))
+        ));
+    }
 }
@@ -0,0 +1,101 @@
+use bevy::log::debug;
+use semver::Version;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Receives timings of the reads and writes of the settings file
+///
+/// Register a reporter with
+/// [`SettingsPlugin::with_telemetry`](crate::SettingsPlugin::with_telemetry). `name` is the
+/// name of the settings file, which holds all settings types registered with the plugin.
+pub trait SettingsTelemetry {
+    /// Called after the file was serialized and written
+    fn on_save(&self, name: &str, bytes: usize, duration: Duration);
+
+    /// Called after the file was read and deserialized
+    fn on_load(&self, name: &str, bytes: usize, duration: Duration);
+}
+
+/// Telemetry reporter that logs every read and write at debug level
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingTelemetry;
+
+impl SettingsTelemetry for LoggingTelemetry {
+    fn on_save(&self, name: &str, bytes: usize, duration: Duration) {
+        debug!(
+            "Saved settings {} ({} bytes) in {:?}",
+            name, bytes, duration
+        );
+    }
+
+    fn on_load(&self, name: &str, bytes: usize, duration: Duration) {
+        debug!(
+            "Loaded settings {} ({} bytes) in {:?}",
+            name, bytes, duration
+        );
+    }
+}
+
+/// Structured event about a settings section, see [`SettingsObserver`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsObservabilityEvent {
+    /// A section was found in the settings file
+    Load {
+        section: String,
+        /// Schema version the section was saved with, if any
+        version: Option<String>,
+        /// Size of the section as compact JSON
+        bytes: usize,
+    },
+    /// A section was written to the settings file
+    Save {
+        section: String,
+        /// Size of the section as compact JSON
+        bytes: usize,
+        /// Whether only the fields that differ from the defaults were written
+        is_delta: bool,
+    },
+    /// A section was migrated from an older schema version
+    MigrationRan {
+        section: String,
+        from: Version,
+        to: Version,
+        /// Whether the migration reported changes
+        changed: bool,
+    },
+    /// A section or one of its fields was rejected by validation and replaced by defaults
+    ValidationFailed {
+        section: String,
+        /// Path of the rejected field, `None` if the whole section was rejected
+        field: Option<String>,
+        error: String,
+    },
+}
+
+/// Receives structured events about loading, migrating, validating and saving sections
+///
+/// Register an observer with
+/// [`SettingsPlugin::with_observability`](crate::SettingsPlugin::with_observability). Unlike
+/// [`SettingsTelemetry`], events are reported per section.
+pub trait SettingsObserver {
+    /// Called for every event, on the thread that loads or saves the settings
+    fn on_event(&self, event: SettingsObservabilityEvent);
+}
+
+/// Observer that ignores all events
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl SettingsObserver for NoopObserver {
+    fn on_event(&self, _event: SettingsObservabilityEvent) {}
+}
+
+/// Observer that collects all events, useful in tests
+#[derive(Debug, Clone, Default)]
+pub struct CollectingObserver(pub Arc<Mutex<Vec<SettingsObservabilityEvent>>>);
+
+impl SettingsObserver for CollectingObserver {
+    fn on_event(&self, event: SettingsObservabilityEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
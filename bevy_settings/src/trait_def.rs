@@ -1,5 +1,7 @@
+use crate::{SettingsError, VersionedMigrationFn};
 use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use std::any::TypeId;
 
 /// Trait for settings that can be managed by the settings system
 ///
@@ -16,5 +18,149 @@ pub trait Settings:
     Resource + Serialize + for<'de> Deserialize<'de> + Default + Clone + PartialEq
 {
     /// Get the type name of the settings struct
+    ///
+    /// The lowercase type name is used as the section name in the settings file. It may only
+    /// contain ASCII letters, digits, `_` and `-`, and must not start with `_`, which is reserved
+    /// for metadata. Registering a type with an invalid name panics in debug builds.
     fn type_name() -> &'static str;
+
+    /// Get a human-readable name for displaying the settings in a UI
+    ///
+    /// Defaults to [`Settings::type_name`]. When deriving, override it with
+    /// `#[settings(display_name = "Audio")]`.
+    fn display_name() -> &'static str {
+        Self::type_name()
+    }
+
+    /// Get the [`TypeId`] of the settings struct for identifying it at runtime
+    ///
+    /// Named to not be confused with [`Any::type_id`](std::any::Any::type_id), which every
+    /// settings type implements as well.
+    fn settings_type_id() -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    /// Get a JSON Schema describing the stored delta of the settings
+    ///
+    /// When a schema is returned and the plugin is built with
+    /// [`SettingsPlugin::with_schema_validation`](crate::SettingsPlugin::with_schema_validation),
+    /// a delta that doesn't match the schema is rejected and the defaults are used instead.
+    /// When deriving, `#[settings(schema)]` generates a basic schema from the field types.
+    fn schema() -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Apply the field-level changes between the schema version `from` and the current one
+    ///
+    /// Runs before the [`MigrationFn`](crate::MigrationFn) of types registered with
+    /// [`SettingsPlugin::register_with_version`](crate::SettingsPlugin::register_with_version),
+    /// so the migration sees the current set of fields. Returns whether `delta` changed.
+    /// When deriving, it is generated from the field attributes:
+    /// - `#[settings(added_in = "2.0.0")]` inserts the default value of the field if `from` is
+    ///   older than `2.0.0` and the delta doesn't contain the field
+    /// - `#[settings(removed_in = "3.0.0")]` deletes the field from the delta if `from` is older
+    ///   than `3.0.0`
+    /// - `#[settings(renamed_from = "old_name", in_version = "2.0.0")]` renames `old_name` to the
+    ///   field if `from` is older than `2.0.0`
+    fn migrate_fields(_from: &semver::Version, _delta: &mut serde_json::Value) -> bool {
+        false
+    }
+
+    /// Get the migrations for ranges of stored schema versions
+    ///
+    /// Each migration upgrades deltas saved with a version matched by its range, which it
+    /// receives as well, see [`VersionedMigrationFn`](crate::VersionedMigrationFn). When a
+    /// settings type registered with
+    /// [`SettingsPlugin::register_with_version`](crate::SettingsPlugin::register_with_version)
+    /// is loaded, the migration whose range matches the stored version runs first, followed by
+    /// every migration whose range starts before the registered version, in ascending order.
+    /// The [`MigrationFn`](crate::MigrationFn) passed at registration only runs if none of
+    /// these match. When deriving, use `#[settings(migrations = "path::to::fn")]`.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_settings::{semver::{Version, VersionReq}, SettingsError, VersionedMigrationFn};
+    /// # use serde_json::Value;
+    /// fn from_v1(
+    ///     _from: &Version,
+    ///     _range: &VersionReq,
+    ///     delta: &mut Value,
+    /// ) -> Result<bool, SettingsError> {
+    ///     // Version 2 stores the volume as a fraction instead of a percentage
+    ///     match delta.get("volume").and_then(Value::as_f64) {
+    ///         Some(volume) => {
+    ///             delta["volume"] = (volume / 100.0).into();
+    ///             Ok(true)
+    ///         }
+    ///         None => Ok(false),
+    ///     }
+    /// }
+    ///
+    /// fn from_v2(
+    ///     _from: &Version,
+    ///     _range: &VersionReq,
+    ///     delta: &mut Value,
+    /// ) -> Result<bool, SettingsError> {
+    ///     // Version 3 renamed "volume" to "master"
+    ///     match delta.as_object_mut().and_then(|delta| delta.remove("volume")) {
+    ///         Some(volume) => {
+    ///             delta["master"] = volume;
+    ///             Ok(true)
+    ///         }
+    ///         None => Ok(false),
+    ///     }
+    /// }
+    ///
+    /// fn audio_migrations() -> Vec<(VersionReq, VersionedMigrationFn)> {
+    ///     vec![
+    ///         (VersionReq::parse(">=1.0.0, <2.0.0").unwrap(), from_v1),
+    ///         (VersionReq::parse(">=2.0.0, <3.0.0").unwrap(), from_v2),
+    ///     ]
+    /// }
+    /// ```
+    fn migrations() -> Vec<(semver::VersionReq, VersionedMigrationFn)> {
+        Vec::new()
+    }
+
+    /// Get the names of the fields that hold sets, such as a `HashSet`
+    ///
+    /// The delta of a set field is stored as `{"added": [...], "removed": [...]}` relative to the
+    /// default set instead of the whole set. When deriving, mark the fields with
+    /// `#[settings(set)]`.
+    fn set_fields() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Get the names of the fields that are stored even if they equal their default value
+    ///
+    /// Useful for fields like a timestamp of the last session, which should always be present in
+    /// the settings file. When deriving, mark the fields with `#[settings(always_persist)]`.
+    fn always_persist_fields() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Get the names of the fields whose values must not appear in log output, like API keys
+    ///
+    /// Their values are replaced with `"[REDACTED]"` in the messages logged by the plugin. When
+    /// deriving, mark the fields with `#[settings(secret)]`.
+    fn secret_fields() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Get the `(alias, field)` pairs of fields renamed with `#[serde(alias = "...")]`
+    ///
+    /// Stored deltas are rewritten to use the current field name when loaded, so the old key
+    /// doesn't stay in the settings file. Generated by the derive macro from the serde
+    /// attributes.
+    fn serde_aliases() -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Create settings from a partial JSON object merged into the defaults
+    ///
+    /// Fields missing from `partial` keep their default values, nested objects are merged
+    /// recursively. Useful for applying patches received over the network.
+    fn merge_partial(partial: serde_json::Value) -> Result<Self, SettingsError> {
+        crate::storage::merge_with_defaults(Some(&partial))
+    }
 }
@@ -0,0 +1,37 @@
+use crate::Settings;
+use bevy::prelude::*;
+use std::sync::Arc;
+
+/// Message sent when changed settings were rejected by a validator and reverted
+///
+/// See [`SettingsPlugin::register_validator`](crate::SettingsPlugin::register_validator).
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct SettingsValidationFailed {
+    /// Type name of the rejected settings
+    pub type_name: &'static str,
+    /// Error returned by the validator
+    pub reason: String,
+}
+
+/// Checks changed settings before they are saved
+pub(crate) type SettingsValidator<T> = Arc<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// Inserts a validator into the [`SettingsValidators`] of its type when the plugin is built
+pub(crate) type ValidatorRegistration = Arc<dyn Fn(&mut App) + Send + Sync>;
+
+/// Validators registered for a settings type
+#[derive(Resource)]
+pub(crate) struct SettingsValidators<T: Settings>(pub Vec<SettingsValidator<T>>);
+
+impl<T: Settings> Default for SettingsValidators<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Settings> SettingsValidators<T> {
+    /// Run all validators, returning the first error
+    pub(crate) fn validate(&self, settings: &T) -> Result<(), String> {
+        self.0.iter().try_for_each(|validator| validator(settings))
+    }
+}
@@ -0,0 +1,148 @@
+use crate::storage::{FileWatcher, SettingsManager, StorageStats};
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How often the hot reload thread checks the settings file for modifications
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+const HOT_RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Message sent when the settings file was modified outside of the app
+///
+/// Only sent when the plugin is built with
+/// [`SettingsPlugin::with_watch_interval`](crate::SettingsPlugin::with_watch_interval).
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct SettingsFileChanged {
+    /// Path of the modified settings file
+    pub path: PathBuf,
+}
+
+/// Polls the modification time of the settings file
+#[derive(Resource)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(crate) struct SettingsFileWatch {
+    timer: Timer,
+    watcher: FileWatcher,
+    /// Number of saves seen on the last poll, to ignore writes of the app itself
+    saves: u64,
+}
+
+impl SettingsFileWatch {
+    pub(crate) fn new(
+        interval: Duration,
+        debounce: Duration,
+        path: &Path,
+        stats: &StorageStats,
+    ) -> Self {
+        let mut watcher = FileWatcher::new(debounce);
+        // Record the current state of the file, so only later changes are reported
+        watcher.should_reload(path);
+
+        Self {
+            timer: Timer::new(interval, TimerMode::Repeating),
+            watcher,
+            saves: stats.saves,
+        }
+    }
+}
+
+/// System that checks the settings file for modifications every watch interval
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn poll_settings_file(
+    time: Res<Time<Real>>,
+    mut watch: ResMut<SettingsFileWatch>,
+    manager: Res<SettingsManager>,
+    stats: Res<StorageStats>,
+    mut changed: MessageWriter<SettingsFileChanged>,
+) {
+    if !watch.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let path = manager.storage.get_path();
+    let modified = watch.watcher.should_reload(&path);
+
+    // The file was written by the app itself since the last poll
+    if watch.saves != stats.saves {
+        watch.saves = stats.saves;
+        return;
+    }
+
+    if modified {
+        changed.write(SettingsFileChanged { path });
+    }
+}
+
+/// The file system can't be polled on WebAssembly, so changes are never reported
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn poll_settings_file() {}
+
+/// Receives modifications of the settings file from a background thread
+///
+/// Only inserted when the plugin is built with
+/// [`SettingsPlugin::with_hot_reload`](crate::SettingsPlugin::with_hot_reload).
+#[derive(Resource)]
+pub(crate) struct HotReload {
+    receiver: Mutex<Receiver<()>>,
+    /// Contents of the settings file if it was modified since the last frame
+    pub settings: Option<Map<String, Value>>,
+}
+
+impl HotReload {
+    /// Start watching the settings file at `path` on a background thread
+    ///
+    /// Modifications are reported at most once per `debounce`. The thread stops at the first
+    /// modification after the resource was dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn spawn(path: PathBuf, debounce: Duration) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = FileWatcher::new(debounce);
+        // Record the current state of the file, so only later changes are reported
+        watcher.should_reload(&path);
+
+        std::thread::Builder::new()
+            .name("bevy_settings hot reload".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(HOT_RELOAD_POLL_INTERVAL);
+                if watcher.should_reload(&path) && sender.send(()).is_err() {
+                    break;
+                }
+            })?;
+
+        Ok(Self {
+            receiver: Mutex::new(receiver),
+            settings: None,
+        })
+    }
+}
+
+/// System that reads the settings file when the hot reload thread reported a modification
+///
+/// Writes of the app itself are reported too. They reload the values the settings already
+/// have, which doesn't change the resources.
+pub(crate) fn receive_hot_reload(
+    mut reload: ResMut<HotReload>,
+    manager: Res<SettingsManager>,
+    mut stats: ResMut<StorageStats>,
+) {
+    reload.settings = None;
+    let receiver = reload
+        .receiver
+        .get_mut()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if receiver.try_iter().count() == 0 {
+        return;
+    }
+
+    // A file that can't be read, e.g. while it is being written, keeps the current settings
+    match manager.storage.load_all(&mut stats) {
+        Ok(all_settings) => {
+            manager.seed(&all_settings);
+            reload.settings = Some(all_settings);
+        }
+        Err(e) => warn!("Failed to hot reload settings: {}", e),
+    }
+}
@@ -1,8 +1,21 @@
+use bevy::log::tracing::{self, field::Field, Event, Subscriber};
+use bevy::log::tracing_subscriber::{layer::Context, prelude::*, Layer};
 use bevy::prelude::*;
-use bevy_settings::{prelude::*, Settings};
+use bevy_settings::{
+    flush_settings_now, list_profiles, prelude::*, semver::Version, CollectingObserver,
+    DowngradePolicy, ExternalEditPolicy, FileEncoding, JsonOptions, JsonSortMode,
+    MigrationErrorPolicy, MigrationHelpers, MigrationLog, SectionSortOrder, Settings,
+    SettingsChangeSource, SettingsChanged, SettingsFileChanged, SettingsHistory,
+    SettingsLoadPending, SettingsLoadedEvent, SettingsObservabilityEvent, SettingsTelemetry,
+    SettingsValidationFailed, SettingsVersionInfo, SharedSettings, StorageStats, VersionInfo,
+    IMPLICIT_INITIAL_VERSION,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
 struct TestSettings {
@@ -206,5 +219,3766 @@ fn test_binary_format() {
     let settings_file = get_test_path(test_name).join("TestSettings.bin");
     assert!(settings_file.exists());
 
+    // Load the binary file in a new app
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Binary)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 999);
+    assert_eq!(settings.name, "binary");
+
+    cleanup_test(test_name);
+}
+
+fn write_settings_file(test_name: &str, content: &str) {
+    let dir = get_test_path(test_name);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("TestSettings.json"), content).unwrap();
+}
+
+#[test]
+fn test_strict_version_check_falls_back_to_defaults() {
+    let test_name = "test_strict_version_check_falls_back_to_defaults";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "version": "99.0.0", "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .version("1.0.0")
+            .with_strict_version_check()
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 42);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_lenient_version_check_loads_newer_file() {
+    let test_name = "test_lenient_version_check_loads_newer_file";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "version": "99.0.0", "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .version("1.0.0")
+            .with_lenient_version_check()
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 7);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(display_name = "Audio")]
+struct AudioSettings {
+    master: f32,
+}
+
+#[test]
+fn test_display_name_defaults_to_type_name() {
+    assert_eq!(TestSettings::display_name(), "TestSettings");
+    assert_eq!(TestSettings::display_name(), TestSettings::type_name());
+}
+
+#[test]
+fn test_display_name_override() {
+    assert_eq!(AudioSettings::type_name(), "AudioSettings");
+    assert_eq!(AudioSettings::display_name(), "Audio");
+}
+
+#[derive(Resource, Default)]
+struct ObservedValue(Option<i32>);
+
+#[test]
+fn test_register_lazy_defers_loading() {
+    let test_name = "test_register_lazy_defers_loading";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register_lazy::<TestSettings>(),
+        )
+        .init_resource::<ObservedValue>()
+        .add_systems(
+            Update,
+            |settings: Res<TestSettings>, mut observed: ResMut<ObservedValue>| {
+                observed.0 = Some(settings.value);
+            },
+        );
+
+    // Nothing is loaded while building the plugin
+    assert!(app.world().get_resource::<TestSettings>().is_none());
+    assert!(app
+        .world()
+        .get_resource::<SettingsLoadPending<TestSettings>>()
+        .is_some());
+
+    // A file written after the plugin was built is still picked up
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    app.update();
+
+    assert_eq!(app.world().resource::<ObservedValue>().0, Some(7));
+    assert!(app
+        .world()
+        .get_resource::<SettingsLoadPending<TestSettings>>()
+        .is_none());
+
+    cleanup_test(test_name);
+}
+
+fn runtime_defaults() -> TestSettings {
+    TestSettings {
+        value: 1080,
+        name: "runtime".to_string(),
+    }
+}
+
+#[test]
+fn test_register_with_default_fn_uses_custom_default() {
+    let test_name = "test_register_with_default_fn_uses_custom_default";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_default_fn(runtime_defaults),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(*settings, runtime_defaults());
+
+    // Resetting to the custom default removes the file instead of persisting a delta
+    {
+        let mut settings = app.world_mut().resource_mut::<TestSettings>();
+        settings.value = 720;
+    }
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.json");
+    let content = fs::read_to_string(&settings_file).unwrap();
+    assert!(content.contains("720"));
+    assert!(!content.contains("runtime"));
+
+    *app.world_mut().resource_mut::<TestSettings>() = runtime_defaults();
+    app.update();
+    assert!(!settings_file.exists());
+
+    cleanup_test(test_name);
+}
+
+fn read_settings_file(test_name: &str) -> serde_json::Value {
+    let content = fs::read_to_string(get_test_path(test_name).join("TestSettings.json")).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+fn double_value(_from: &Version, delta: &mut serde_json::Value) -> Result<bool, SettingsError> {
+    if let Some(value) = delta.get("value").and_then(serde_json::Value::as_i64) {
+        delta["value"] = (value * 2).into();
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn migrate_nothing(_from: &Version, _delta: &mut serde_json::Value) -> Result<bool, SettingsError> {
+    Ok(false)
+}
+
+#[test]
+fn test_register_with_version_runs_migration() {
+    let test_name = "test_register_with_version_runs_migration";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("2.0.0", double_value),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 14);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "2.0.0");
+    assert_eq!(file["testsettings"]["value"], 14);
+
+    cleanup_test(test_name);
+}
+
+fn master_to_fraction(
+    from: &Version,
+    delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    if from.major >= 2 {
+        return Ok(false);
+    }
+    Ok(MigrationHelpers::transform_field(
+        delta,
+        "master",
+        |value| (value.as_f64().unwrap_or(100.0) / 100.0).into(),
+    ))
+}
+
+#[test]
+fn test_migration_transforms_field() {
+    let test_name = "test_migration_transforms_field";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "audiosettings": "1.0.0" }, "audiosettings": { "master": 25 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<AudioSettings>("2.0.0", master_to_fraction),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<AudioSettings>().master, 0.25);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["audiosettings"]["master"], 0.25);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_register_with_version_bumps_version_without_changes() {
+    let test_name = "test_register_with_version_bumps_version_without_changes";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("2.0.0", migrate_nothing),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "2.0.0");
+    assert_eq!(file["testsettings"]["value"], 7);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_storage_stats_resource() {
+    let test_name = "test_storage_stats_resource";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    let stats = app.world().resource::<StorageStats>();
+    assert_eq!(stats.loads, 1);
+    assert_eq!(stats.saves, 0);
+
+    for value in 1..=2 {
+        app.world_mut().resource_mut::<TestSettings>().value = value;
+        app.update();
+    }
+
+    let stats = app.world().resource::<StorageStats>();
+    assert_eq!(stats.loads, 1);
+    assert_eq!(stats.saves, 2);
+    assert!(stats.bytes_written > 0);
+
+    cleanup_test(test_name);
+}
+
+fn failing_migration(
+    _from: &Version,
+    delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    delta["value"] = 0.into();
+    Err(SettingsError::Migration("unsupported layout".to_string()))
+}
+
+fn app_with_failing_migration(test_name: &str, policy: MigrationErrorPolicy) -> App {
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_migration_error_policy(policy)
+            .register_with_version::<TestSettings>("2.0.0", failing_migration),
+    );
+    app
+}
+
+#[test]
+fn test_migration_error_policy_use_delta() {
+    let test_name = "test_migration_error_policy_use_delta";
+    cleanup_test(test_name);
+
+    let mut app = app_with_failing_migration(test_name, MigrationErrorPolicy::WarnAndUseDelta);
+    app.update();
+
+    // The partial changes of the failed migration are discarded
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    // The old version is kept, so the migration runs again on the next load
+    app.world_mut().resource_mut::<TestSettings>().name = "changed".to_string();
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["_versions"]["testsettings"],
+        "1.0.0"
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_migration_error_policy_use_defaults() {
+    let test_name = "test_migration_error_policy_use_defaults";
+    cleanup_test(test_name);
+
+    let mut app = app_with_failing_migration(test_name, MigrationErrorPolicy::WarnAndUseDefaults);
+    app.update();
+
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+
+    app.world_mut().resource_mut::<TestSettings>().name = "changed".to_string();
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["_versions"]["testsettings"],
+        "1.0.0"
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+#[should_panic(expected = "Failed to migrate settings")]
+fn test_migration_error_policy_panic() {
+    let test_name = "test_migration_error_policy_panic";
+    cleanup_test(test_name);
+
+    app_with_failing_migration(test_name, MigrationErrorPolicy::Panic);
+}
+
+#[test]
+fn test_merge_partial() {
+    let settings = TestSettings::merge_partial(serde_json::json!({ "value": 7 })).unwrap();
+    assert_eq!(settings.value, 7);
+    assert_eq!(settings.name, TestSettings::default().name);
+
+    // Patches with the wrong type are rejected
+    assert!(TestSettings::merge_partial(serde_json::json!({ "value": "seven" })).is_err());
+}
+
+#[test]
+fn test_initial_capacity_does_not_change_behavior() {
+    let test_name = "test_initial_capacity_does_not_change_behavior";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_initial_capacity(64)
+            .register::<TestSettings>()
+            .register::<AudioSettings>(),
+    );
+
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.5;
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["testsettings"]["value"], 7);
+    assert_eq!(file["audiosettings"]["master"], 0.5);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_new_from_env() {
+    let test_name = "test_new_from_env";
+    cleanup_test(test_name);
+
+    let path = get_test_path(test_name).to_str().unwrap().to_string();
+    let env = |format: &'static str| {
+        let path = path.clone();
+        move |name: &str| match name {
+            "BEVY_SETTINGS_NAME" => Some("EnvSettings".to_string()),
+            "BEVY_SETTINGS_FORMAT" => Some(format.to_string()),
+            "BEVY_SETTINGS_PATH" => Some(path.clone()),
+            _ => None,
+        }
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SettingsPlugin::new_from_env_with(env("binary")).register::<TestSettings>());
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    assert!(get_test_path(test_name).join("EnvSettings.bin").exists());
+
+    // Unknown formats fall back to JSON
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SettingsPlugin::new_from_env_with(env("xml")).register::<AudioSettings>());
+    app.update();
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.5;
+    app.update();
+
+    assert!(get_test_path(test_name).join("EnvSettings.json").exists());
+
+    // Missing variables produce defaults instead of panicking
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SettingsPlugin::new_from_env_with(|_| None).register::<TestSettings>());
+    app.update();
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_pre_load_hook_renames_section() {
+    let test_name = "test_pre_load_hook_renames_section";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "legacysettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_pre_load_hook(|root| {
+                if let Some(section) = root.remove("legacysettings") {
+                    root.insert("testsettings".to_string(), section);
+                }
+            })
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_section_whitelist_drops_other_sections() {
+    let test_name = "test_section_whitelist_drops_other_sections";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{
+            "_versions": { "testsettings": "1.0.0", "oldsettings": "3.0.0" },
+            "testsettings": { "value": 7 },
+            "oldsettings": { "enabled": true }
+        }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_section_whitelist(&["testsettings"])
+            .register_with_version::<TestSettings>("1.0.0", migrate_nothing),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    app.world_mut().resource_mut::<TestSettings>().value = 8;
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["testsettings"]["value"], 8);
+    assert_eq!(file["_versions"]["testsettings"], "1.0.0");
+    assert!(file.get("oldsettings").is_none());
+    assert!(file["_versions"].get("oldsettings").is_none());
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_section_blacklist_keeps_defaults() {
+    let test_name = "test_section_blacklist_keeps_defaults";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "testsettings": { "value": 7 }, "audiosettings": { "master": 0.5 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_section_blacklist(&["testsettings"])
+            .register::<TestSettings>()
+            .register::<AudioSettings>(),
+    );
+
+    app.update();
+
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+    assert_eq!(app.world().resource::<AudioSettings>().master, 0.5);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_file_creation_callback() {
+    let test_name = "test_file_creation_callback";
+    cleanup_test(test_name);
+    let created = Arc::new(Mutex::new(Vec::new()));
+
+    let mut app = App::new();
+    let recorded = created.clone();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_file_creation_callback(move |path| {
+                recorded.lock().unwrap().push(path.to_path_buf())
+            })
+            .register::<TestSettings>(),
+    );
+    app.update();
+    assert!(created.lock().unwrap().is_empty());
+
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 8;
+    app.update();
+
+    assert_eq!(
+        *created.lock().unwrap(),
+        vec![get_test_path(test_name).join("TestSettings.json")]
+    );
+
+    cleanup_test(test_name);
+}
+
+/// Build and update an app with `plugin` storing its files in the directory of the test
+fn app_with(test_name: &str, plugin: SettingsPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(plugin.with_base_path(get_test_path(test_name).to_str().unwrap()));
+    app.update();
+    app
+}
+
+#[test]
+fn test_utf16_file_encoding_round_trip() {
+    let test_name = "test_utf16_file_encoding_round_trip";
+    cleanup_test(test_name);
+
+    let mut app = app_with(
+        test_name,
+        SettingsPlugin::new("TestSettings")
+            .with_file_encoding(FileEncoding::Utf16Le)
+            .register::<TestSettings>(),
+    );
+    app.world_mut().resource_mut::<TestSettings>().name = "Grüße, 世界".to_string();
+    app.update();
+
+    let content = fs::read(get_test_path(test_name).join("TestSettings.json")).unwrap();
+    assert!(content.starts_with(&[0xFF, 0xFE]));
+    assert!(serde_json::from_slice::<serde_json::Value>(&content).is_err());
+
+    let app = app_with(
+        test_name,
+        SettingsPlugin::new("TestSettings")
+            .with_file_encoding(FileEncoding::Utf16Le)
+            .register::<TestSettings>(),
+    );
+    assert_eq!(app.world().resource::<TestSettings>().name, "Grüße, 世界");
+
+    cleanup_test(test_name);
+}
+
+/// Collects the messages of all log events emitted while it is the default subscriber
+#[derive(Clone, Default)]
+struct LogCapture(Arc<Mutex<Vec<String>>>);
+
+impl LogCapture {
+    /// Run `f` with the captured log as the thread's default subscriber
+    fn capture<R>(&self, f: impl FnOnce() -> R) -> R {
+        let subscriber = bevy::log::tracing_subscriber::registry().with(self.clone());
+        tracing::subscriber::with_default(subscriber, f)
+    }
+
+    fn contains(&self, needle: &str) -> bool {
+        self.0.lock().unwrap().iter().any(|m| m.contains(needle))
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogCapture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        struct MessageVisitor<'a>(&'a mut String);
+
+        impl tracing::field::Visit for MessageVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.push_str(&format!("{:?}", value));
+                }
+            }
+        }
+
+        let mut message = String::new();
+        event.record(&mut MessageVisitor(&mut message));
+        self.0.lock().unwrap().push(message);
+    }
+}
+
+#[test]
+fn test_unparseable_stored_version_is_treated_as_none() {
+    let test_name = "test_unparseable_stored_version_is_treated_as_none";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "invalid" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let log = LogCapture::default();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    log.capture(|| {
+        app.add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register_with_version::<TestSettings>("2.0.0", double_value),
+        );
+    });
+
+    assert!(log.contains("Unparseable stored version 'invalid' for section 'testsettings'"));
+
+    // Without a stored version the delta is migrated from the implicit initial version
+    app.update();
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 14);
+    assert_eq!(settings.name, "default");
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "2.0.0");
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_settings_type_id() {
+    use std::any::TypeId;
+
+    assert_eq!(
+        TestSettings::settings_type_id(),
+        TypeId::of::<TestSettings>()
+    );
+    assert_ne!(
+        TestSettings::settings_type_id(),
+        AudioSettings::settings_type_id()
+    );
+}
+
+#[test]
+fn test_json_comments_are_stripped() {
+    let test_name = "test_json_comments_are_stripped";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"// Settings for the test
+{
+    # lowered for testing
+    "testsettings": {
+        // "value": 1,
+        "value": 7
+    }
+}"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_json_comments(true)
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "empty settings version")]
+fn test_register_with_empty_version_panics_in_debug() {
+    let _ =
+        SettingsPlugin::new("TestSettings").register_with_version::<TestSettings>("", double_value);
+}
+
+#[test]
+fn test_register_optional_version_none() {
+    let test_name = "test_register_optional_version_none";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_optional_version::<TestSettings>(None, double_value),
+    );
+
+    app.update();
+
+    // Without a version the migration never runs
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    app.world_mut().resource_mut::<TestSettings>().value = 8;
+    app.update();
+
+    // The stored version is left untouched
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "1.0.0");
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_write_delay() {
+    let test_name = "test_write_delay";
+    cleanup_test(test_name);
+
+    let delay = std::time::Duration::from_millis(200);
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_write_delay(delay)
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.json");
+    assert!(!settings_file.exists());
+
+    std::thread::sleep(delay);
+    app.update();
+
+    assert!(settings_file.exists());
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 7);
+
+    cleanup_test(test_name);
+}
+
+fn debounced_app(test_name: &str, debounce: Duration) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_save_debounce(debounce)
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_save_debounce_batches_changes() {
+    let test_name = "test_save_debounce_batches_changes";
+    cleanup_test(test_name);
+
+    let debounce = Duration::from_millis(150);
+    let mut app = debounced_app(test_name, debounce);
+    let saves = app.world().resource::<StorageStats>().saves;
+
+    // Dragging a slider changes the settings every frame
+    for value in 1..=5 {
+        app.world_mut().resource_mut::<TestSettings>().value = value;
+        app.update();
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.json");
+    assert!(!settings_file.exists());
+    assert_eq!(app.world().resource::<StorageStats>().saves, saves);
+
+    std::thread::sleep(debounce);
+    app.update();
+    app.update();
+
+    assert_eq!(app.world().resource::<StorageStats>().saves, saves + 1);
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 5);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_flush_settings_now_bypasses_debounce() {
+    let test_name = "test_flush_settings_now_bypasses_debounce";
+    cleanup_test(test_name);
+
+    let mut app = debounced_app(test_name, Duration::from_secs(60));
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.json");
+    assert!(!settings_file.exists());
+
+    flush_settings_now(app.world_mut());
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 7);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct VideoSettings {
+    quality: u8,
+    fullscreen: bool,
+}
+
+#[test]
+fn test_schema_validation_replaces_invalid_fields() {
+    let test_name = "test_schema_validation_replaces_invalid_fields";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "videosettings": { "quality": 300, "fullscreen": true } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_schema_validation()
+            .register::<VideoSettings>(),
+    );
+
+    app.update();
+
+    // The out-of-range quality falls back to its default, the valid field is kept
+    let settings = app.world().resource::<VideoSettings>();
+    assert_eq!(settings.quality, 0);
+    assert!(settings.fullscreen);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_registered_version() {
+    let test_name = "test_registered_version";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("2.1.0", migrate_nothing)
+            .register::<AudioSettings>(),
+    );
+
+    assert_eq!(
+        SettingsPlugin::registered_version::<TestSettings>(app.world()),
+        Some(Version::new(2, 1, 0))
+    );
+    assert_eq!(
+        SettingsPlugin::registered_version::<AudioSettings>(app.world()),
+        None
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Keybind {
+    action: String,
+    key: String,
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct InputSettings {
+    keybinds: Vec<Keybind>,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        let bind = |action: &str, key: &str| Keybind {
+            action: action.to_string(),
+            key: key.to_string(),
+        };
+        Self {
+            keybinds: vec![bind("jump", "Space"), bind("crouch", "C")],
+        }
+    }
+}
+
+#[test]
+fn test_merge_arrays_by_index() {
+    let test_name = "test_merge_arrays_by_index";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "inputsettings": { "keybinds": [{ "key": "W" }, {}] } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_merge_arrays_by_index(true)
+            .register::<InputSettings>(),
+    );
+
+    app.update();
+
+    let keybinds = &app.world().resource::<InputSettings>().keybinds;
+    assert_eq!(keybinds[0].action, "jump");
+    assert_eq!(keybinds[0].key, "W");
+    assert_eq!(keybinds[1], InputSettings::default().keybinds[1]);
+
+    cleanup_test(test_name);
+}
+
+#[cfg(feature = "bevy_state")]
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+enum GameState {
+    #[default]
+    Menu,
+    Level,
+}
+
+#[cfg(feature = "bevy_state")]
+#[test]
+fn test_register_for_state() {
+    use bevy::state::app::StatesPlugin;
+
+    let test_name = "test_register_for_state";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<GameState>()
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register_for_state::<TestSettings, _>(GameState::Level),
+        );
+
+    app.update();
+    assert!(app.world().get_resource::<TestSettings>().is_none());
+
+    // Entering the state loads the settings
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Level);
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    app.world_mut().resource_mut::<TestSettings>().value = 9;
+
+    // Leaving the state saves them and removes the resource
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Menu);
+    app.update();
+    assert!(app.world().get_resource::<TestSettings>().is_none());
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 9);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_migration_dry_run() {
+    let test_name = "test_migration_dry_run";
+    cleanup_test(test_name);
+    let content = r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#;
+    write_settings_file(test_name, content);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_migration_dry_run()
+            .register_with_version::<TestSettings>("2.0.0", double_value),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 14);
+
+    let settings_file = get_test_path(test_name).join("TestSettings.json");
+    assert_eq!(fs::read_to_string(&settings_file).unwrap(), content);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct GraphicsSettings {
+    quality: u8,
+    #[settings(added_in = "2.0.0")]
+    vsync: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            quality: 2,
+            vsync: true,
+        }
+    }
+}
+
+#[test]
+fn test_added_in_inserts_default_for_old_versions() {
+    let mut delta = serde_json::json!({ "quality": 3 });
+    assert!(GraphicsSettings::migrate_fields(
+        &Version::new(1, 0, 0),
+        &mut delta
+    ));
+    assert_eq!(delta, serde_json::json!({ "quality": 3, "vsync": true }));
+
+    let mut delta = serde_json::json!({ "quality": 3 });
+    assert!(!GraphicsSettings::migrate_fields(
+        &Version::new(2, 0, 0),
+        &mut delta
+    ));
+    assert_eq!(delta, serde_json::json!({ "quality": 3 }));
+
+    let mut delta = serde_json::json!({ "vsync": false });
+    assert!(!GraphicsSettings::migrate_fields(
+        &Version::new(1, 0, 0),
+        &mut delta
+    ));
+    assert_eq!(delta, serde_json::json!({ "vsync": false }));
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct NetworkSettings {
+    port: u16,
+    #[serde(skip)]
+    #[settings(removed_in = "3.0.0")]
+    legacy_relay: bool,
+}
+
+#[test]
+fn test_removed_in_strips_field_from_old_versions() {
+    let mut delta = serde_json::json!({ "port": 7777, "legacy_relay": true });
+    assert!(NetworkSettings::migrate_fields(
+        &Version::new(2, 5, 0),
+        &mut delta
+    ));
+    assert_eq!(delta, serde_json::json!({ "port": 7777 }));
+
+    let mut delta = serde_json::json!({ "port": 7777, "legacy_relay": true });
+    assert!(!NetworkSettings::migrate_fields(
+        &Version::new(3, 0, 0),
+        &mut delta
+    ));
+    assert_eq!(
+        delta,
+        serde_json::json!({ "port": 7777, "legacy_relay": true })
+    );
+}
+
+#[test]
+fn test_removed_in_cleans_saved_file() {
+    let test_name = "test_removed_in_cleans_saved_file";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{
+            "_versions": { "networksettings": "2.0.0" },
+            "networksettings": { "port": 7777, "legacy_relay": true }
+        }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<NetworkSettings>("3.0.0", migrate_nothing),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<NetworkSettings>().port, 7777);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["networksettings"], serde_json::json!({ "port": 7777 }));
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct ControlsSettings {
+    #[settings(renamed_from = "mouse_speed", in_version = "2.0.0")]
+    #[settings(added_in = "2.0.0")]
+    sensitivity: f32,
+}
+
+#[test]
+fn test_renamed_from_renames_field() {
+    let test_name = "test_renamed_from_renames_field";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "controlssettings": "1.0.0" }, "controlssettings": { "mouse_speed": 1.5 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<ControlsSettings>("2.0.0", migrate_nothing),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<ControlsSettings>().sensitivity, 1.5);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(
+        file["controlssettings"],
+        serde_json::json!({ "sensitivity": 1.5 })
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_added_in_runs_before_migration() {
+    let test_name = "test_added_in_runs_before_migration";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "graphicssettings": "1.0.0" }, "graphicssettings": { "quality": 3 } }"#,
+    );
+
+    fn disable_vsync(
+        _from: &Version,
+        delta: &mut serde_json::Value,
+    ) -> Result<bool, SettingsError> {
+        Ok(MigrationHelpers::transform_field(delta, "vsync", |_| {
+            false.into()
+        }))
+    }
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<GraphicsSettings>("2.0.0", disable_vsync),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<GraphicsSettings>();
+    assert_eq!(settings.quality, 3);
+    assert!(!settings.vsync);
+
+    let log = app.world().resource::<MigrationLog>();
+    assert_eq!(
+        log.entries[0].changes,
+        serde_json::json!({ "vsync": false })
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_migration_log() {
+    let test_name = "test_migration_log";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("2.0.0", double_value),
+    );
+
+    app.update();
+
+    let log = app.world().resource::<MigrationLog>();
+    assert_eq!(log.entries.len(), 1);
+
+    let entry = &log.entries[0];
+    assert_eq!(entry.type_name, "TestSettings");
+    assert_eq!(entry.from_version, Some(Version::new(1, 0, 0)));
+    assert_eq!(entry.to_version, Version::new(2, 0, 0));
+    assert_eq!(entry.changes, serde_json::json!({ "value": 14 }));
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct VolumeSettings {
+    volume: u8,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self { volume: 80 }
+    }
+}
+
+impl Settings for VolumeSettings {
+    fn type_name() -> &'static str {
+        "VolumeSettings"
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "volume": { "type": "integer", "maximum": 100 }
+            }
+        }))
+    }
+}
+
+fn load_volume_settings(test_name: &str, content: &str) -> VolumeSettings {
+    cleanup_test(test_name);
+    write_settings_file(test_name, content);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_schema_validation()
+            .register::<VolumeSettings>(),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<VolumeSettings>().clone();
+    cleanup_test(test_name);
+    settings
+}
+
+#[test]
+fn test_schema_rejects_invalid_delta() {
+    // 150 fits into a u8, but violates the maximum of the schema
+    let settings = load_volume_settings(
+        "test_schema_rejects_invalid_delta",
+        r#"{ "volumesettings": { "volume": 150 } }"#,
+    );
+    assert_eq!(settings.volume, 80);
+
+    let settings = load_volume_settings(
+        "test_schema_accepts_valid_delta",
+        r#"{ "volumesettings": { "volume": 90 } }"#,
+    );
+    assert_eq!(settings.volume, 90);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(schema)]
+struct DisplaySettings {
+    brightness: f32,
+    vsync: bool,
+    monitor: String,
+    resolution: (u32, u32),
+    profile: Option<u8>,
+}
+
+#[test]
+fn test_derived_schema() {
+    assert_eq!(
+        DisplaySettings::schema(),
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "brightness": { "type": "number" },
+                "vsync": { "type": "boolean" },
+                "monitor": { "type": "string" },
+                "resolution": { "type": "array" },
+                "profile": {}
+            }
+        }))
+    );
+    assert_eq!(TestSettings::schema(), None);
+}
+
+#[test]
+fn test_max_file_size_uses_defaults() {
+    let test_name = "test_max_file_size_uses_defaults";
+    cleanup_test(test_name);
+    let padding = " ".repeat(2048);
+    write_settings_file(
+        test_name,
+        &format!(r#"{{ "testsettings": {{ "value": 7 }} }}{}"#, padding),
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_max_file_size(1024)
+            .register::<TestSettings>(),
+    );
+
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 42);
+
+    cleanup_test(test_name);
+}
+
+fn load_audio_settings(
+    test_name: &str,
+    master: &str,
+    options: Option<JsonOptions>,
+) -> AudioSettings {
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        &format!(r#"{{ "audiosettings": {{ "master": {master} }} }}"#),
+    );
+
+    let mut plugin = SettingsPlugin::new("TestSettings")
+        .format(SerializationFormat::Json)
+        .with_base_path(get_test_path(test_name).to_str().unwrap())
+        .register::<AudioSettings>();
+    if let Some(options) = options {
+        plugin = plugin.with_serde_options(options);
+    }
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin);
+    app.update();
+
+    let settings = app.world().resource::<AudioSettings>().clone();
+    cleanup_test(test_name);
+    settings
+}
+
+#[test]
+fn test_serde_options_reject_nan_by_default() {
+    let settings = load_audio_settings("test_serde_options_reject_nan_by_default", "null", None);
+    assert_eq!(settings.master, 0.0);
+}
+
+#[test]
+fn test_serde_options_reject_inf_by_default() {
+    let settings = load_audio_settings("test_serde_options_reject_inf_by_default", "1e40", None);
+    assert_eq!(settings.master, 0.0);
+}
+
+#[test]
+fn test_serde_options_allow_inf() {
+    let options = JsonOptions {
+        reject_inf: false,
+        ..Default::default()
+    };
+    let settings = load_audio_settings("test_serde_options_allow_inf", "1e40", Some(options));
+    assert_eq!(settings.master, f32::INFINITY);
+}
+
+fn compressed_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_compression_threshold(256)
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_compression_threshold() {
+    let test_name = "test_compression_threshold";
+    cleanup_test(test_name);
+    let path = get_test_path(test_name).join("TestSettings.json");
+
+    // Small files stay plain JSON
+    let mut app = compressed_app(test_name);
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 7);
+
+    // Large files are compressed
+    let long_name = "settings ".repeat(100);
+    app.world_mut().resource_mut::<TestSettings>().name = long_name.clone();
+    app.update();
+    let content = fs::read(&path).unwrap();
+    assert_eq!(&content[..2], &[0x1f, 0x8b]);
+    assert!(content.len() < long_name.len());
+
+    // Compressed files are decompressed on load
+    let app = compressed_app(test_name);
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 7);
+    assert_eq!(settings.name, long_name);
+
+    cleanup_test(test_name);
+}
+
+fn load_downgraded(test_name: &str, policy: DowngradePolicy) -> (App, LogCapture) {
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "3.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let log = LogCapture::default();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    log.capture(|| {
+        app.add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .with_downgrade_policy(policy)
+                .register_with_version::<TestSettings>("2.0.0", double_value),
+        );
+    });
+    app.update();
+    (app, log)
+}
+
+#[test]
+fn test_downgrade_warns_and_loads_newer_delta() {
+    let test_name = "test_downgrade_warns_and_loads_newer_delta";
+    let (app, log) = load_downgraded(test_name, DowngradePolicy::Warn);
+
+    assert!(log.contains(
+        "Settings for TestSettings were saved by a newer version (3.0.0) than the current binary (2.0.0)"
+    ));
+
+    // The migration doesn't run backwards, and the newer version stays recorded
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+    assert_eq!(
+        read_settings_file(test_name)["_versions"]["testsettings"],
+        "3.0.0"
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_downgrade_policy_use_defaults() {
+    let test_name = "test_downgrade_policy_use_defaults";
+    let (mut app, log) = load_downgraded(test_name, DowngradePolicy::UseDefaults);
+
+    assert!(log.contains("were saved by a newer version"));
+    assert_eq!(app.world().resource::<TestSettings>().value, 42);
+
+    // Saving with the older binary keeps the newer section in the file
+    app.world_mut().resource_mut::<TestSettings>().value = 5;
+    app.update();
+    let file = read_settings_file(test_name);
+    assert_eq!(file["testsettings"]["value"], 7);
+    assert_eq!(file["_versions"]["testsettings"], "3.0.0");
+
+    // Upgrading again loads the newer settings
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("3.0.0", double_value),
+    );
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct PlayerOneSettings {
+    volume: f32,
+    name: String,
+    color: String,
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct PlayerTwoSettings {
+    volume: f32,
+    name: String,
+    controller: u8,
+}
+
+#[test]
+fn test_copy_settings_copies_shared_fields() {
+    let test_name = "test_copy_settings_copies_shared_fields";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<PlayerOneSettings>()
+            .register::<PlayerTwoSettings>(),
+    );
+    app.update();
+
+    app.insert_resource(PlayerOneSettings {
+        volume: 0.5,
+        name: "One".to_string(),
+        color: "red".to_string(),
+    });
+    app.world_mut()
+        .resource_mut::<PlayerTwoSettings>()
+        .controller = 2;
+    app.world_mut()
+        .commands()
+        .copy_settings::<PlayerOneSettings, PlayerTwoSettings>();
+    app.update();
+
+    let settings = app.world().resource::<PlayerTwoSettings>();
+    assert_eq!(settings.volume, 0.5);
+    assert_eq!(settings.name, "One");
+    assert_eq!(settings.controller, 2);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["playertwosettings"]["name"], "One");
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct DottedSettings;
+
+impl Settings for DottedSettings {
+    fn type_name() -> &'static str {
+        "game.audio"
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct MetadataSettings;
+
+impl Settings for MetadataSettings {
+    fn type_name() -> &'static str {
+        "_audio"
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Invalid settings section name 'game.audio'")]
+fn test_register_rejects_dotted_section_name() {
+    let _ = SettingsPlugin::new("TestSettings").register::<DottedSettings>();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Invalid settings section name '_audio'")]
+fn test_register_rejects_metadata_section_name() {
+    let _ = SettingsPlugin::new("TestSettings")
+        .register_with_version::<MetadataSettings>("1.0.0", migrate_nothing);
+}
+
+#[derive(Resource, Default)]
+struct ChangedFiles(Vec<PathBuf>);
+
+fn collect_changed_files(
+    mut reader: MessageReader<SettingsFileChanged>,
+    mut files: ResMut<ChangedFiles>,
+) {
+    files
+        .0
+        .extend(reader.read().map(|changed| changed.path.clone()));
+}
+
+#[test]
+fn test_watch_interval_reports_external_changes() {
+    let test_name = "test_watch_interval_reports_external_changes";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 1 } }"#);
+    let path = get_test_path(test_name).join("TestSettings.json");
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(60),
+        ))
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .with_watch_interval(Duration::from_millis(100))
+                .register::<TestSettings>(),
+        )
+        .init_resource::<ChangedFiles>()
+        .add_systems(Update, collect_changed_files);
+
+    for _ in 0..3 {
+        app.update();
+    }
+    assert!(app.world().resource::<ChangedFiles>().0.is_empty());
+
+    // Writes of the app itself are not reported
+    app.world_mut().resource_mut::<TestSettings>().value = 2;
+    for _ in 0..3 {
+        app.update();
+    }
+    assert!(app.world().resource::<ChangedFiles>().0.is_empty());
+
+    std::thread::sleep(Duration::from_millis(10));
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 3 } }"#);
+
+    // The change is only noticed once the timer finishes
+    let mut updates = 0;
+    while app.world().resource::<ChangedFiles>().0.is_empty() {
+        app.update();
+        updates += 1;
+        assert!(updates <= 2, "change was not reported");
+    }
+    assert_eq!(app.world().resource::<ChangedFiles>().0, vec![path]);
+
+    cleanup_test(test_name);
+}
+
+fn delete_settings_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_delete_settings_removes_file() {
+    let test_name = "test_delete_settings_removes_file";
+    cleanup_test(test_name);
+    let path = get_test_path(test_name).join("TestSettings.json");
+
+    let mut app = delete_settings_app(test_name);
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+    assert!(path.exists());
+
+    app.world_mut().commands().delete_settings::<TestSettings>();
+    app.update();
+
+    assert!(!path.exists());
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+
+    let app = delete_settings_app(test_name);
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+
+    cleanup_test(test_name);
+}
+
+fn write_sections(test_name: &str, order: Option<SectionSortOrder>) -> String {
+    cleanup_test(test_name);
+
+    let mut plugin = SettingsPlugin::new("TestSettings")
+        .format(SerializationFormat::Json)
+        .with_base_path(get_test_path(test_name).to_str().unwrap())
+        .register::<VideoSettings>()
+        .register::<AudioSettings>()
+        .register::<TestSettings>();
+    if let Some(order) = order {
+        plugin = plugin.with_section_sort(order);
+    }
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin);
+    app.update();
+
+    app.world_mut().resource_mut::<VideoSettings>().quality = 3;
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.5;
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let content = fs::read_to_string(get_test_path(test_name).join("TestSettings.json")).unwrap();
+    cleanup_test(test_name);
+    content
+}
+
+fn section_positions(content: &str) -> [usize; 3] {
+    ["\"audiosettings\"", "\"testsettings\"", "\"videosettings\""]
+        .map(|section| content.find(section).unwrap())
+}
+
+#[test]
+fn test_section_sort_registration_order() {
+    let content = write_sections("test_section_sort_registration_order", None);
+    let [audio, test, video] = section_positions(&content);
+    assert!(video < audio && audio < test, "{}", content);
+}
+
+#[test]
+fn test_section_sort_alphabetical() {
+    let content = write_sections(
+        "test_section_sort_alphabetical",
+        Some(SectionSortOrder::Alphabetical),
+    );
+    let [audio, test, video] = section_positions(&content);
+    assert!(audio < test && test < video, "{}", content);
+}
+
+#[test]
+fn test_json_sorting_alphabetical() {
+    let test_name = "test_json_sorting_alphabetical";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_json_sorting(JsonSortMode::Alphabetical)
+            .register::<TestSettings>()
+            .register::<AudioSettings>(),
+    );
+    app.update();
+
+    let mut settings = app.world_mut().resource_mut::<TestSettings>();
+    settings.value = 7;
+    settings.name = "sorted".to_string();
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.5;
+    app.update();
+
+    let content = fs::read_to_string(get_test_path(test_name).join("TestSettings.json")).unwrap();
+    let position = |key: &str| content.find(&format!("\"{}\"", key)).unwrap();
+    assert!(
+        position("audiosettings") < position("testsettings"),
+        "{}",
+        content
+    );
+    assert!(position("name") < position("value"), "{}", content);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(schema)]
+struct ScoreSettings {
+    #[serde(with = "bevy_settings::big_int")]
+    #[settings(big_int)]
+    high_score: u128,
+}
+
+fn score_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_schema_validation()
+            .register::<ScoreSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_big_int_round_trip() {
+    let test_name = "test_big_int_round_trip";
+    cleanup_test(test_name);
+
+    let mut app = score_app(test_name);
+    app.world_mut().resource_mut::<ScoreSettings>().high_score = u128::MAX;
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["scoresettings"]["high_score"], u128::MAX.to_string());
+
+    let app = score_app(test_name);
+    assert_eq!(
+        app.world().resource::<ScoreSettings>().high_score,
+        u128::MAX
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct CameraSettings {
+    offset: Vec3,
+    fov: f32,
+    tint: Color,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::new(0.0, 2.0, -5.0),
+            fov: 70.0,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+fn camera_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<CameraSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_math_type_delta() {
+    let test_name = "test_math_type_delta";
+    cleanup_test(test_name);
+
+    let mut app = camera_app(test_name);
+    let mut settings = app.world_mut().resource_mut::<CameraSettings>();
+    settings.offset.y = 3.0;
+    settings.tint.set_alpha(0.5);
+    app.update();
+
+    // Vectors serialize as arrays, which are stored whole, colors as objects, of which only the
+    // changed components are stored
+    let file = read_settings_file(test_name);
+    assert_eq!(
+        file["camerasettings"],
+        serde_json::json!({
+            "offset": [0.0, 3.0, -5.0],
+            "tint": { "LinearRgba": { "alpha": 0.5 } },
+        })
+    );
+
+    let app = camera_app(test_name);
+    assert_eq!(
+        *app.world().resource::<CameraSettings>(),
+        CameraSettings {
+            offset: Vec3::new(0.0, 3.0, -5.0),
+            fov: 70.0,
+            tint: Color::WHITE.with_alpha(0.5),
+        }
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_observer_receives_all_events() {
+    let test_name = "test_observer_receives_all_events";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{
+            "_versions": { "testsettings": "1.0.0" },
+            "testsettings": { "value": 7 },
+            "displaysettings": { "vsync": "yes" }
+        }"#,
+    );
+    let observer = CollectingObserver::default();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_observability(observer.clone())
+            .with_schema_validation()
+            .register_with_version::<TestSettings>("2.0.0", double_value)
+            .register::<DisplaySettings>(),
+    );
+    app.update();
+
+    let events = observer.0.lock().unwrap().clone();
+    assert!(events.contains(&SettingsObservabilityEvent::Load {
+        section: "testsettings".to_string(),
+        version: Some("1.0.0".to_string()),
+        bytes: r#"{"value":7}"#.len(),
+    }));
+    assert!(events.contains(&SettingsObservabilityEvent::MigrationRan {
+        section: "testsettings".to_string(),
+        from: Version::new(1, 0, 0),
+        to: Version::new(2, 0, 0),
+        changed: true,
+    }));
+    assert!(events.contains(&SettingsObservabilityEvent::Save {
+        section: "testsettings".to_string(),
+        bytes: r#"{"value":14}"#.len(),
+        is_delta: true,
+    }));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        SettingsObservabilityEvent::ValidationFailed { section, field, .. }
+            if section == "displaysettings" && field.as_deref() == Some("/vsync")
+    )));
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_shared_settings_visible_to_other_threads() {
+    let test_name = "test_shared_settings_visible_to_other_threads";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_shared::<TestSettings>(),
+    );
+    app.update();
+
+    let handle = app
+        .world()
+        .resource::<SharedSettings<TestSettings>>()
+        .handle();
+    assert_eq!(handle.read().unwrap().value, 42);
+
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let value = std::thread::spawn(move || handle.read().unwrap().value)
+        .join()
+        .unwrap();
+    assert_eq!(value, 7);
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 7);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+enum DifficultySettings {
+    Easy,
+    #[default]
+    Normal,
+    Hard {
+        permadeath: bool,
+        lives: u8,
+    },
+}
+
+fn difficulty_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<DifficultySettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_enum_settings() {
+    let test_name = "test_enum_settings";
+    cleanup_test(test_name);
+
+    let mut app = difficulty_app(test_name);
+    assert_eq!(
+        *app.world().resource::<DifficultySettings>(),
+        DifficultySettings::Normal
+    );
+
+    *app.world_mut().resource_mut::<DifficultySettings>() = DifficultySettings::Easy;
+    app.update();
+    assert_eq!(read_settings_file(test_name)["difficultysettings"], "Easy");
+
+    let hard = DifficultySettings::Hard {
+        permadeath: true,
+        lives: 3,
+    };
+    *app.world_mut().resource_mut::<DifficultySettings>() = hard.clone();
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["difficultysettings"],
+        serde_json::json!({ "Hard": { "permadeath": true, "lives": 3 } })
+    );
+
+    let mut app = difficulty_app(test_name);
+    assert_eq!(*app.world().resource::<DifficultySettings>(), hard);
+
+    // Back to the default variant, nothing is left to save
+    *app.world_mut().resource_mut::<DifficultySettings>() = DifficultySettings::Normal;
+    app.update();
+    assert!(!get_test_path(test_name).join("TestSettings.json").exists());
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+enum WindowModeSettings {
+    Windowed { width: u32, height: u32 },
+    Fullscreen { monitor: u8 },
+}
+
+impl Default for WindowModeSettings {
+    fn default() -> Self {
+        Self::Windowed {
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+#[test]
+fn test_enum_settings_change_struct_variant() {
+    let test_name = "test_enum_settings_change_struct_variant";
+    cleanup_test(test_name);
+
+    let plugin = || {
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<WindowModeSettings>()
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+
+    *app.world_mut().resource_mut::<WindowModeSettings>() =
+        WindowModeSettings::Fullscreen { monitor: 1 };
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["windowmodesettings"],
+        serde_json::json!({ "Fullscreen": { "monitor": 1 } })
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+    assert_eq!(
+        *app.world().resource::<WindowModeSettings>(),
+        WindowModeSettings::Fullscreen { monitor: 1 }
+    );
+
+    // Within the default variant only the changed fields are stored
+    *app.world_mut().resource_mut::<WindowModeSettings>() = WindowModeSettings::Windowed {
+        width: 1920,
+        height: 720,
+    };
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["windowmodesettings"],
+        serde_json::json!({ "Windowed": { "width": 1920 } })
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_section_comment() {
+    let test_name = "test_section_comment";
+    cleanup_test(test_name);
+
+    let plugin = || {
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_section_comment("testsettings", "General test settings")
+            .register::<TestSettings>()
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let content = fs::read_to_string(get_test_path(test_name).join("TestSettings.json")).unwrap();
+    let comment = content.find("\"_comment_testsettings\": \"General test settings\"");
+    assert!(comment.unwrap() < content.find("\"testsettings\"").unwrap());
+
+    // The comment is not mistaken for a section when loading
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+    app.world_mut().resource_mut::<TestSettings>().value = 8;
+    app.update();
+
+    let file = read_settings_file(test_name);
+    let keys: Vec<&String> = file.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["_comment_testsettings", "testsettings"]);
+
+    cleanup_test(test_name);
+}
+
+/// Save a value of 7 with external edit detection, change it to 9 behind the app's back and
+/// load the file again
+fn edit_externally(test_name: &str, policy: ExternalEditPolicy) -> App {
+    let plugin = || {
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_external_edit_detection(policy)
+            .register::<TestSettings>()
+    };
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let mut file = read_settings_file(test_name);
+    assert!(file["_content_hash"]["testsettings"].is_string());
+    file["testsettings"]["value"] = 9.into();
+    write_settings_file(test_name, &file.to_string());
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins(plugin());
+    app.update();
+    app
+}
+
+#[test]
+fn test_external_edit_accepted() {
+    let test_name = "test_external_edit_accepted";
+    cleanup_test(test_name);
+
+    let log = LogCapture::default();
+    let app = log.capture(|| edit_externally(test_name, ExternalEditPolicy::Accept));
+
+    assert!(log.contains("External edit detected for section 'testsettings'"));
+    assert_eq!(app.world().resource::<TestSettings>().value, 9);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_external_edit_rejected() {
+    let test_name = "test_external_edit_rejected";
+    cleanup_test(test_name);
+
+    let log = LogCapture::default();
+    let app = log.capture(|| edit_externally(test_name, ExternalEditPolicy::RejectAndUseDefaults));
+
+    assert!(log.contains("External edit detected for section 'testsettings'"));
+    assert_eq!(app.world().resource::<TestSettings>().value, 42);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_unedited_file_is_not_reported() {
+    let test_name = "test_unedited_file_is_not_reported";
+    cleanup_test(test_name);
+
+    let plugin = || {
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_external_edit_detection(ExternalEditPolicy::RejectAndUseDefaults)
+            .register::<TestSettings>()
+    };
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(plugin());
+    app.update();
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let log = LogCapture::default();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    log.capture(|| app.add_plugins(plugin()));
+
+    assert!(!log.contains("External edit detected"));
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[settings(schema)]
+struct ModSettings {
+    #[settings(set)]
+    disabled_mods: HashSet<String>,
+    load_order: Vec<String>,
+}
+
+impl Default for ModSettings {
+    fn default() -> Self {
+        Self {
+            disabled_mods: ["legacy_ui", "debug_tools"].map(String::from).into(),
+            load_order: vec!["core".to_string()],
+        }
+    }
+}
+
+fn mod_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_schema_validation()
+            .register::<ModSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_set_field_delta() {
+    let test_name = "test_set_field_delta";
+    cleanup_test(test_name);
+    assert_eq!(ModSettings::set_fields(), ["disabled_mods"]);
+
+    let mut app = mod_app(test_name);
+    let mut settings = app.world_mut().resource_mut::<ModSettings>();
+    settings.disabled_mods.remove("legacy_ui");
+    settings.disabled_mods.insert("hd_textures".to_string());
+    app.update();
+
+    assert_eq!(
+        read_settings_file(test_name)["modsettings"],
+        serde_json::json!({
+            "disabled_mods": { "added": ["hd_textures"], "removed": ["legacy_ui"] }
+        })
+    );
+
+    let app = mod_app(test_name);
+    assert_eq!(
+        app.world().resource::<ModSettings>().disabled_mods,
+        ["debug_tools", "hd_textures"].map(String::from).into()
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Clone, Default)]
+struct RecordingTelemetry {
+    saves: Arc<Mutex<Vec<(String, usize)>>>,
+    loads: Arc<Mutex<Vec<(String, usize)>>>,
+}
+
+impl SettingsTelemetry for RecordingTelemetry {
+    fn on_save(&self, name: &str, bytes: usize, _duration: Duration) {
+        self.saves.lock().unwrap().push((name.to_string(), bytes));
+    }
+
+    fn on_load(&self, name: &str, bytes: usize, _duration: Duration) {
+        self.loads.lock().unwrap().push((name.to_string(), bytes));
+    }
+}
+
+fn telemetry_app(test_name: &str, telemetry: &RecordingTelemetry) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_telemetry(telemetry.clone())
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_telemetry_reports_saves_and_loads() {
+    let test_name = "test_telemetry_reports_saves_and_loads";
+    cleanup_test(test_name);
+    let path = get_test_path(test_name).join("TestSettings.json");
+
+    let telemetry = RecordingTelemetry::default();
+    let mut app = telemetry_app(test_name, &telemetry);
+    assert!(telemetry.loads.lock().unwrap().is_empty());
+
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+
+    let size = fs::metadata(&path).unwrap().len() as usize;
+    assert_eq!(
+        *telemetry.saves.lock().unwrap(),
+        vec![("TestSettings".to_string(), size)]
+    );
+
+    telemetry_app(test_name, &telemetry);
+    assert_eq!(
+        *telemetry.loads.lock().unwrap(),
+        vec![("TestSettings".to_string(), size)]
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_missing_version_migrates_from_implicit_initial_version() {
+    let test_name = "test_missing_version_migrates_from_implicit_initial_version";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("1.0.0", double_value),
+    );
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 14);
+
+    let log = app.world().resource::<MigrationLog>();
+    assert_eq!(log.entries[0].from_version, Some(IMPLICIT_INITIAL_VERSION));
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "1.0.0");
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_missing_versions_are_written_without_changes() {
+    let test_name = "test_missing_versions_are_written_without_changes";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("1.0.0", migrate_nothing),
+    );
+    app.update();
+
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["testsettings"], "1.0.0");
+    assert_eq!(file["testsettings"]["value"], 7);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+#[cfg(feature = "bevy_asset")]
+fn test_register_as_asset() {
+    let test_name = "test_register_as_asset";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(bevy::asset::AssetPlugin {
+            file_path: "tests/assets".to_string(),
+            ..default()
+        })
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register_as_asset::<AudioSettings>("audio.settings.json"),
+        );
+
+    assert!(app
+        .world()
+        .contains_resource::<SettingsLoadPending<AudioSettings>>());
+
+    // Assets are loaded in the background
+    for _ in 0..500 {
+        app.update();
+        if app.world().contains_resource::<AudioSettings>() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+
+    let settings = app.world().resource::<AudioSettings>();
+    assert_eq!(settings.master, 0.25);
+    assert!(!app
+        .world()
+        .contains_resource::<SettingsLoadPending<AudioSettings>>());
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct OtherAudioSettings {
+    muted: bool,
+}
+
+impl Settings for OtherAudioSettings {
+    fn type_name() -> &'static str {
+        "AudioSettings"
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Settings section 'audiosettings' is already used")]
+fn test_register_rejects_duplicate_sections() {
+    let _ = SettingsPlugin::new("TestSettings")
+        .register::<AudioSettings>()
+        .register_lazy::<OtherAudioSettings>();
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn test_register_ignores_duplicate_sections_in_release() {
+    let test_name = "test_register_ignores_duplicate_sections_in_release";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<AudioSettings>()
+            .register::<OtherAudioSettings>(),
+    );
+    app.update();
+    assert!(!app.world().contains_resource::<OtherAudioSettings>());
+
+    // Only the first type writes the section
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.25;
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["audiosettings"]["master"],
+        0.25
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_schema_file_output() {
+    let test_name = "test_schema_file_output";
+    cleanup_test(test_name);
+    let schema_path = get_test_path(test_name).join("schema/settings.schema.json");
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_schema_file_output(&schema_path)
+            .register::<DisplaySettings>()
+            .register::<AudioSettings>(),
+    );
+
+    let content = std::fs::read_to_string(&schema_path).unwrap();
+    let schema: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(
+        schema["properties"]["displaysettings"],
+        DisplaySettings::schema().unwrap()
+    );
+    // Types without a schema accept any value
+    assert_eq!(schema["properties"]["audiosettings"], serde_json::json!({}));
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct HudSettings {
+    #[serde(alias = "hud_scale")]
+    scale: f32,
+    visible: bool,
+}
+
+#[test]
+fn test_serde_alias_key_is_removed_from_file() {
+    let test_name = "test_serde_alias_key_is_removed_from_file";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "hudsettings": { "hud_scale": 1.5 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<HudSettings>(),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<HudSettings>().scale, 1.5);
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["hudsettings"], serde_json::json!({ "scale": 1.5 }));
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(schema)]
+struct MatchmakingSettings {
+    #[serde(with = "bevy_settings::human_duration")]
+    #[settings(duration_format = "human")]
+    search_timeout: Duration,
+}
+
+#[test]
+fn test_human_duration_round_trip() {
+    let test_name = "test_human_duration_round_trip";
+    cleanup_test(test_name);
+
+    let matchmaking_app = || {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .with_schema_validation()
+                .register::<MatchmakingSettings>(),
+        );
+        app.update();
+        app
+    };
+
+    let mut app = matchmaking_app();
+    app.world_mut()
+        .resource_mut::<MatchmakingSettings>()
+        .search_timeout = Duration::from_millis(1500);
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["matchmakingsettings"]["search_timeout"], "1s500ms");
+
+    let app = matchmaking_app();
+    assert_eq!(
+        app.world().resource::<MatchmakingSettings>().search_timeout,
+        Duration::from_millis(1500)
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct ProfileSettings {
+    nickname: String,
+    #[settings(always_persist)]
+    total_playtime: u64,
+    #[settings(skip_delta = false)]
+    last_level: u32,
+}
+
+#[test]
+fn test_always_persist_field_is_saved_at_default() {
+    let test_name = "test_always_persist_field_is_saved_at_default";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<ProfileSettings>(),
+    );
+    app.update();
+
+    app.world_mut().resource_mut::<ProfileSettings>().nickname = "Tim".to_string();
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(
+        file["profilesettings"],
+        serde_json::json!({ "nickname": "Tim", "total_playtime": 0 })
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_truncated_binary_file_is_moved_aside() {
+    let test_name = "test_truncated_binary_file_is_moved_aside";
+    cleanup_test(test_name);
+
+    let binary_app = || {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Binary)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register::<TestSettings>(),
+        );
+        app.update();
+        app
+    };
+
+    let mut app = binary_app();
+    app.world_mut().resource_mut::<TestSettings>().name = "truncated".to_string();
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.bin");
+    let content = fs::read(&settings_file).unwrap();
+    fs::write(&settings_file, &content[..content.len() - 8]).unwrap();
+
+    let app = binary_app();
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+    assert!(!settings_file.exists());
+    assert_eq!(
+        fs::read(get_test_path(test_name).join("TestSettings.bin.corrupt")).unwrap(),
+        content[..content.len() - 8]
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct ServiceSettings {
+    endpoint: String,
+    #[settings(secret)]
+    api_key: String,
+    retries: u32,
+}
+
+fn rotate_api_key(_from: &Version, delta: &mut serde_json::Value) -> Result<bool, SettingsError> {
+    delta["api_key"] = "sk-rotated-secret".into();
+    delta["retries"] = 3.into();
+    Ok(true)
+}
+
+#[test]
+fn test_secret_fields_are_redacted_in_logs() {
+    let test_name = "test_secret_fields_are_redacted_in_logs";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{
+            "_versions": { "servicesettings": "1.0.0" },
+            "servicesettings": { "api_key": "sk-old-secret", "retries": 1 }
+        }"#,
+    );
+
+    let log = LogCapture::default();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    log.capture(|| {
+        app.add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .with_migration_dry_run()
+                .register_with_version::<ServiceSettings>("2.0.0", rotate_api_key),
+        );
+    });
+
+    assert_eq!(
+        app.world().resource::<ServiceSettings>().api_key,
+        "sk-rotated-secret"
+    );
+    assert!(log.contains(r#""api_key":"[REDACTED]""#));
+    assert!(log.contains(r#""retries":3"#));
+    assert!(!log.contains("secret"));
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_settings_version_info() {
+    let test_name = "test_settings_version_info";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "testsettings": "1.0.0" }, "testsettings": { "value": 7 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<TestSettings>("2.0.0", double_value)
+            .register_with_version::<GraphicsSettings>("1.0.0", migrate_nothing)
+            .register::<AudioSettings>(),
+    );
+
+    let info = app.world().resource::<SettingsVersionInfo>();
+    assert_eq!(
+        info.get::<TestSettings>(),
+        Some(&VersionInfo {
+            target_version: Some(Version::new(2, 0, 0)),
+            file_version: Some(Version::new(1, 0, 0)),
+            migrated: true,
+        })
+    );
+    // Freshly installed, nothing was stored yet
+    assert_eq!(
+        info.get::<GraphicsSettings>(),
+        Some(&VersionInfo {
+            target_version: Some(Version::new(1, 0, 0)),
+            file_version: None,
+            migrated: false,
+        })
+    );
+    assert_eq!(info.get::<AudioSettings>(), Some(&VersionInfo::default()));
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_concurrent_saves_leave_valid_file() {
+    let test_name = "test_concurrent_saves_leave_valid_file";
+    cleanup_test(test_name);
+
+    let writers: Vec<_> = (0..4)
+        .map(|writer| {
+            std::thread::spawn(move || {
+                let mut app = App::new();
+                app.add_plugins(MinimalPlugins).add_plugins(
+                    SettingsPlugin::new("TestSettings")
+                        .format(SerializationFormat::Json)
+                        .with_base_path(get_test_path(test_name).to_str().unwrap())
+                        .register::<TestSettings>(),
+                );
+                app.update();
+                for i in 0..20 {
+                    let mut settings = app.world_mut().resource_mut::<TestSettings>();
+                    settings.value = writer * 100 + i;
+                    settings.name = "x".repeat(i as usize * 50);
+                    app.update();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // Whichever write came last, the file is complete
+    let file = read_settings_file(test_name);
+    assert_eq!(file["testsettings"]["value"].as_i64().unwrap() % 100, 19);
+    let leftovers = fs::read_dir(get_test_path(test_name))
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("tmp".as_ref()))
+        .count();
+    assert_eq!(leftovers, 0);
+
+    cleanup_test(test_name);
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Yaml)
+            .version("1.0.0")
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>()
+            .register::<GraphicsSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_format_round_trip() {
+    let test_name = "test_yaml_format_round_trip";
+    cleanup_test(test_name);
+
+    let mut app = yaml_app(test_name);
+    {
+        let mut settings = app.world_mut().resource_mut::<TestSettings>();
+        settings.value = 7;
+        settings.name = "yaml".to_string();
+    }
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.yaml");
+    let content = fs::read_to_string(&settings_file).unwrap();
+    assert!(content.contains("testsettings:\n  value: 7\n  name: yaml\n"));
+
+    let app = yaml_app(test_name);
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 7);
+    assert_eq!(settings.name, "yaml");
+
+    cleanup_test(test_name);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_anchors_in_hand_written_file() {
+    let test_name = "test_yaml_anchors_in_hand_written_file";
+    cleanup_test(test_name);
+    let settings_file = get_test_path(test_name).join("TestSettings.yaml");
+    fs::create_dir_all(get_test_path(test_name)).unwrap();
+    fs::write(
+        &settings_file,
+        r#"# Shared between sections
+base: &base
+  quality: 4
+graphicssettings:
+  <<: *base
+  vsync: false
+testsettings:
+  name: &name anchored
+  value: 3
+"#,
+    )
+    .unwrap();
+
+    let app = yaml_app(test_name);
+    let graphics = app.world().resource::<GraphicsSettings>();
+    assert_eq!(graphics.quality, 4);
+    assert!(!graphics.vsync);
+    assert_eq!(app.world().resource::<TestSettings>().name, "anchored");
+
+    // An alias without an anchor is an error, not a panic
+    fs::write(&settings_file, "testsettings:\n  name: *missing\n").unwrap();
+    let app = yaml_app(test_name);
+    assert_eq!(
+        *app.world().resource::<TestSettings>(),
+        TestSettings::default()
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_env_overrides_are_not_saved() {
+    let test_name = "test_env_overrides_are_not_saved";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 7 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_env_prefix("envtest")
+            .with_env_vars([
+                ("ENVTEST_TEST_NAME".to_string(), "from_env".to_string()),
+                (
+                    "ENVTEST_AUDIOSETTINGS_MASTER".to_string(),
+                    "0.5".to_string(),
+                ),
+            ])
+            .register::<TestSettings>()
+            .register::<AudioSettings>(),
+    );
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 7);
+    assert_eq!(settings.name, "from_env");
+    assert_eq!(app.world().resource::<AudioSettings>().master, 0.5);
+
+    app.world_mut().resource_mut::<TestSettings>().value = 9;
+    app.update();
+
+    let file = read_settings_file(test_name);
+    assert_eq!(file["testsettings"], serde_json::json!({ "value": 9 }));
+    assert!(file.get("audiosettings").is_none());
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_arg_overrides_apply_after_env() {
+    let test_name = "test_arg_overrides_apply_after_env";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_env_prefix("argtest")
+            .with_env_vars([
+                ("ARGTEST_TEST_VALUE".to_string(), "5".to_string()),
+                ("ARGTEST_TEST_NAME".to_string(), "from_env".to_string()),
+            ])
+            .with_args(["game", "--setting.test.name=from_args"].map(str::to_string))
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app.update();
+
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 5);
+    assert_eq!(settings.name, "from_args");
+    // Overrides don't count as changes, so nothing was saved
+    assert!(!get_test_path(test_name).join("TestSettings.json").exists());
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_settings_profiles() {
+    let test_name = "test_settings_profiles";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+    app.update();
+
+    *app.world_mut().resource_mut::<TestSettings>() = TestSettings {
+        value: 1,
+        name: "competitive".to_string(),
+    };
+    app.world_mut()
+        .commands()
+        .save_profile::<TestSettings>("competitive");
+    app.update();
+
+    app.world_mut().resource_mut::<TestSettings>().value = 2;
+    app.world_mut()
+        .commands()
+        .save_profile::<TestSettings>("casual");
+    app.update();
+
+    assert_eq!(
+        list_profiles::<TestSettings>(app.world()),
+        vec!["casual".to_string(), "competitive".to_string()]
+    );
+
+    app.world_mut()
+        .commands()
+        .load_profile::<TestSettings>("competitive");
+    app.update();
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 1);
+    assert_eq!(settings.name, "competitive");
+
+    app.world_mut()
+        .commands()
+        .load_profile::<TestSettings>("casual");
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+
+    // Invalid names and missing profiles leave the settings untouched
+    app.world_mut()
+        .commands()
+        .load_profile::<TestSettings>("..");
+    app.world_mut()
+        .commands()
+        .load_profile::<TestSettings>("missing");
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+
+    app.world_mut()
+        .commands()
+        .delete_profile::<TestSettings>("casual");
+    app.update();
+    assert_eq!(
+        list_profiles::<TestSettings>(app.world()),
+        vec!["competitive".to_string()]
+    );
+    assert!(!get_test_path(test_name).join("profiles/casual").exists());
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_undo_redo_settings() {
+    let test_name = "test_undo_redo_settings";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_history_depth(2)
+            .register::<TestSettings>(),
+    );
+    app.update();
+
+    for value in [1, 2, 3] {
+        app.world_mut().resource_mut::<TestSettings>().value = value;
+        app.update();
+    }
+
+    // The depth limit dropped the defaults, the oldest value
+    app.world_mut().commands().undo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 2);
+
+    app.world_mut().commands().undo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 1);
+    assert!(!app
+        .world()
+        .resource::<SettingsHistory<TestSettings>>()
+        .can_undo());
+
+    app.world_mut().commands().undo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 1);
+
+    app.world_mut().commands().redo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+
+    // A new change discards the values to redo
+    app.world_mut().resource_mut::<TestSettings>().value = 5;
+    app.update();
+    app.world_mut().commands().redo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 5);
+
+    app.world_mut().commands().undo_settings::<TestSettings>();
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Default)]
+struct ChangeCount(u32);
+
+fn count_settings_changes(settings: Res<TestSettings>, mut count: ResMut<ChangeCount>) {
+    if settings.is_changed() {
+        count.0 += 1;
+    }
+}
+
+#[test]
+fn test_hot_reload_updates_resource() {
+    let test_name = "test_hot_reload_updates_resource";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 1 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .with_hot_reload()
+                .register::<TestSettings>(),
+        )
+        .init_resource::<ChangeCount>()
+        .add_systems(Update, count_settings_changes);
+    app.update();
+    assert_eq!(app.world().resource::<TestSettings>().value, 1);
+    app.world_mut().resource_mut::<ChangeCount>().0 = 0;
+
+    std::thread::spawn(move || {
+        write_settings_file(
+            test_name,
+            r#"{ "testsettings": { "value": 2, "name": "edited" } }"#,
+        );
+    })
+    .join()
+    .unwrap();
+    // Give the watcher thread time to notice the modification
+    std::thread::sleep(Duration::from_millis(300));
+
+    app.update();
+    app.update();
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 2);
+    assert_eq!(settings.name, "edited");
+    assert_eq!(app.world().resource::<ChangeCount>().0, 1);
+
+    // The reload was saved, which reloads the same values without changing the resource
+    std::thread::sleep(Duration::from_millis(300));
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<ChangeCount>().0, 1);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_file_watcher_debounce_reloads_once_per_window() {
+    let test_name = "test_file_watcher_debounce_reloads_once_per_window";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 1 } }"#);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .with_hot_reload()
+            .with_file_watcher_debounce(Duration::from_secs(1))
+            .register::<TestSettings>(),
+    );
+    app.update();
+    let loads = app.world().resource::<StorageStats>().loads;
+
+    // An editor writing the file in several steps, each seen by the watcher thread
+    for value in [2, 3, 4] {
+        write_settings_file(
+            test_name,
+            &format!(r#"{{ "testsettings": {{ "value": {} }} }}"#, value),
+        );
+        std::thread::sleep(Duration::from_millis(100));
+        app.update();
+    }
+
+    assert_eq!(app.world().resource::<StorageStats>().loads, loads + 1);
+    assert_eq!(app.world().resource::<TestSettings>().value, 2);
+
+    // The held back modifications are reloaded once the window has passed
+    std::thread::sleep(Duration::from_millis(1000));
+    app.update();
+    assert_eq!(app.world().resource::<StorageStats>().loads, loads + 2);
+    assert_eq!(app.world().resource::<TestSettings>().value, 4);
+
+    cleanup_test(test_name);
+}
+
+fn encrypted_app(test_name: &str, format: SerializationFormat, plugin: SettingsPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        plugin
+            .format(format)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[test]
+fn test_encrypted_settings_round_trip() {
+    for (format, file) in [
+        (SerializationFormat::Json, "TestSettings.json.enc"),
+        (SerializationFormat::Binary, "TestSettings.bin.enc"),
+    ] {
+        let test_name = "test_encrypted_settings_round_trip";
+        cleanup_test(test_name);
+        let key = [42; 32];
+
+        let mut app = encrypted_app(
+            test_name,
+            format,
+            SettingsPlugin::new("TestSettings").with_encryption(key),
+        );
+        app.world_mut().resource_mut::<TestSettings>().name = "secret name".to_string();
+        app.update();
+
+        let content = fs::read(get_test_path(test_name).join(file)).unwrap();
+        assert!(!content
+            .windows("secret name".len())
+            .any(|window| window == b"secret name"));
+
+        let app = encrypted_app(
+            test_name,
+            format,
+            SettingsPlugin::new("TestSettings").with_encryption(key),
+        );
+        assert_eq!(
+            app.world().resource::<TestSettings>().name,
+            "secret name",
+            "{:?}",
+            format
+        );
+
+        cleanup_test(test_name);
+    }
+}
+
+#[test]
+fn test_encrypted_settings_with_wrong_key_are_kept() {
+    let test_name = "test_encrypted_settings_with_wrong_key_are_kept";
+    cleanup_test(test_name);
+    let path = get_test_path(test_name).join("TestSettings.json.enc");
+
+    let mut app = encrypted_app(
+        test_name,
+        SerializationFormat::Json,
+        SettingsPlugin::new("TestSettings").with_encryption([1; 32]),
+    );
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    app.update();
+    let encrypted = fs::read(&path).unwrap();
+
+    let mut app = encrypted_app(
+        test_name,
+        SerializationFormat::Json,
+        SettingsPlugin::new("TestSettings").with_encryption([2; 32]),
+    );
+    assert_eq!(app.world().resource::<TestSettings>().value, 42);
+    // Left in place and not overwritten by changes made with the wrong key
+    app.world_mut().resource_mut::<TestSettings>().value = 8;
+    app.update();
+    assert_eq!(fs::read(&path).unwrap(), encrypted);
+    assert!(!path.with_extension("enc.corrupt").exists());
+
+    let app = encrypted_app(
+        test_name,
+        SerializationFormat::Json,
+        SettingsPlugin::new("TestSettings").with_encryption([1; 32]),
+    );
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+
+    // A file too short to be encrypted is moved aside
+    fs::write(&path, b"abc").unwrap();
+    let app = encrypted_app(
+        test_name,
+        SerializationFormat::Json,
+        SettingsPlugin::new("TestSettings").with_encryption([1; 32]),
+    );
+    assert_eq!(app.world().resource::<TestSettings>().value, 42);
+    assert!(!path.exists());
+    assert_eq!(
+        fs::read(path.with_extension("enc.corrupt")).unwrap(),
+        b"abc"
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Default)]
+struct ValidationFailures(Vec<SettingsValidationFailed>);
+
+fn collect_validation_failures(
+    mut reader: MessageReader<SettingsValidationFailed>,
+    mut failures: ResMut<ValidationFailures>,
+) {
+    failures.0.extend(reader.read().cloned());
+}
+
+#[test]
+fn test_validator_reverts_invalid_changes() {
+    let test_name = "test_validator_reverts_invalid_changes";
+    cleanup_test(test_name);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(
+            SettingsPlugin::new("TestSettings")
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register::<AudioSettings>()
+                .register_validator::<AudioSettings, _>(|audio| {
+                    if audio.master < 0.0 {
+                        return Err(format!("negative volume {}", audio.master));
+                    }
+                    Ok(())
+                })
+                .register_validator::<AudioSettings, _>(|audio| {
+                    if audio.master > 1.0 {
+                        return Err("volume above 1".to_string());
+                    }
+                    Ok(())
+                }),
+        )
+        .init_resource::<ValidationFailures>()
+        .add_systems(Update, collect_validation_failures);
+    app.update();
+
+    app.world_mut().resource_mut::<AudioSettings>().master = 0.5;
+    app.update();
+    assert_eq!(
+        read_settings_file(test_name)["audiosettings"]["master"],
+        0.5
+    );
+
+    app.world_mut().resource_mut::<AudioSettings>().master = -1.0;
+    app.update();
+    assert_eq!(app.world().resource::<AudioSettings>().master, 0.5);
+    assert_eq!(
+        read_settings_file(test_name)["audiosettings"]["master"],
+        0.5
+    );
+
+    app.world_mut().resource_mut::<AudioSettings>().master = 2.0;
+    app.update();
+    assert_eq!(app.world().resource::<AudioSettings>().master, 0.5);
+
+    app.update();
+    assert_eq!(
+        app.world().resource::<ValidationFailures>().0,
+        vec![
+            SettingsValidationFailed {
+                type_name: "AudioSettings",
+                reason: "negative volume -1".to_string(),
+            },
+            SettingsValidationFailed {
+                type_name: "AudioSettings",
+                reason: "volume above 1".to_string(),
+            },
+        ]
+    );
+
+    cleanup_test(test_name);
+}
+
+#[derive(Resource, Default)]
+struct SettingsMessages {
+    loaded: Vec<SettingsLoadedEvent<TestSettings>>,
+    changed: Vec<SettingsChanged<TestSettings>>,
+}
+
+fn collect_settings_messages(
+    mut loaded: MessageReader<SettingsLoadedEvent<TestSettings>>,
+    mut changed: MessageReader<SettingsChanged<TestSettings>>,
+    mut messages: ResMut<SettingsMessages>,
+) {
+    messages.loaded.extend(loaded.read().cloned());
+    messages.changed.extend(changed.read().cloned());
+}
+
+fn messages_app(test_name: &str, plugin: SettingsPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(
+            plugin
+                .format(SerializationFormat::Json)
+                .with_base_path(get_test_path(test_name).to_str().unwrap())
+                .register::<TestSettings>(),
+        )
+        .init_resource::<SettingsMessages>()
+        .add_systems(Update, collect_settings_messages);
+    app
+}
+
+#[test]
+fn test_settings_changed_sent_once_per_save() {
+    let test_name = "test_settings_changed_sent_once_per_save";
+    cleanup_test(test_name);
+    write_settings_file(test_name, r#"{ "testsettings": { "value": 1 } }"#);
+
+    let mut app = messages_app(test_name, SettingsPlugin::new("TestSettings"));
+    app.update();
+    app.update();
+    let messages = app.world().resource::<SettingsMessages>();
+    assert_eq!(messages.loaded.len(), 1);
+    assert_eq!(messages.loaded[0].value.value, 1);
+    assert_eq!(messages.loaded[0].source, SettingsChangeSource::File);
+    assert!(messages.changed.is_empty());
+
+    for value in [2, 3] {
+        app.world_mut().resource_mut::<TestSettings>().value = value;
+        app.update();
+    }
+    app.update();
+    app.update();
+
+    let messages = app.world().resource::<SettingsMessages>();
+    assert_eq!(messages.loaded.len(), 1);
+    let changed: Vec<(i32, SettingsChangeSource)> = messages
+        .changed
+        .iter()
+        .map(|changed| (changed.value.value, changed.source))
+        .collect();
+    assert_eq!(
+        changed,
+        vec![
+            (2, SettingsChangeSource::Command),
+            (3, SettingsChangeSource::Command)
+        ]
+    );
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_settings_loaded_reports_overrides() {
+    let test_name = "test_settings_loaded_reports_overrides";
+    cleanup_test(test_name);
+
+    let mut app = messages_app(
+        test_name,
+        SettingsPlugin::new("TestSettings").with_args(["--setting.test.value=5".to_string()]),
+    );
+    app.update();
+
+    let messages = app.world().resource::<SettingsMessages>();
+    assert_eq!(messages.loaded.len(), 1);
+    assert_eq!(messages.loaded[0].value.value, 5);
+    assert_eq!(messages.loaded[0].source, SettingsChangeSource::ArgOverride);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_settings_changed_waits_for_debounced_write() {
+    let test_name = "test_settings_changed_waits_for_debounced_write";
+    cleanup_test(test_name);
+
+    let debounce = Duration::from_millis(100);
+    let mut app = messages_app(
+        test_name,
+        SettingsPlugin::new("TestSettings").with_save_debounce(debounce),
+    );
+    app.update();
+
+    for value in [2, 3] {
+        app.world_mut().resource_mut::<TestSettings>().value = value;
+        app.update();
+    }
+    app.update();
+    assert!(!get_test_path(test_name).join("TestSettings.json").exists());
+    assert!(app
+        .world()
+        .resource::<SettingsMessages>()
+        .changed
+        .is_empty());
+
+    std::thread::sleep(debounce);
+    app.update();
+    app.update();
+
+    let messages = app.world().resource::<SettingsMessages>();
+    assert_eq!(read_settings_file(test_name)["testsettings"]["value"], 3);
+    assert_eq!(messages.changed.len(), 1);
+    assert_eq!(messages.changed[0].value.value, 3);
+    assert_eq!(messages.changed[0].source, SettingsChangeSource::Command);
+
+    // Flushing by hand sends the message as well
+    app.world_mut().resource_mut::<TestSettings>().value = 4;
+    app.update();
+    flush_settings_now(app.world_mut());
+    app.update();
+    let messages = app.world().resource::<SettingsMessages>();
+    assert_eq!(messages.changed.len(), 2);
+    assert_eq!(messages.changed[1].value.value, 4);
+
+    cleanup_test(test_name);
+}
+
+fn toml_app(test_name: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Toml)
+            .version("1.0.0")
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register::<TestSettings>()
+            .register::<GraphicsSettings>()
+            .register::<TomlKindsSettings>(),
+    );
+    app.update();
+    app
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+enum TomlWindowMode {
+    #[default]
+    Windowed,
+    Fullscreen {
+        monitor: u8,
+    },
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+struct TomlResolution {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct TomlKindsSettings {
+    enabled: bool,
+    count: i64,
+    scale: f64,
+    label: String,
+    levels: Vec<i32>,
+    resolution: TomlResolution,
+    mode: TomlWindowMode,
+    slots: std::collections::HashMap<u32, String>,
+    limit: Option<u8>,
+    optional_levels: Vec<Option<i32>>,
+    pair: (Option<u8>, u8),
+}
+
+impl Default for TomlKindsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 0,
+            scale: 0.0,
+            label: String::new(),
+            levels: Vec::new(),
+            resolution: TomlResolution::default(),
+            mode: TomlWindowMode::default(),
+            slots: std::collections::HashMap::new(),
+            limit: Some(60),
+            optional_levels: Vec::new(),
+            pair: (Some(1), 1),
+        }
+    }
+}
+
+#[test]
+fn test_toml_format_round_trip() {
+    let test_name = "test_toml_format_round_trip";
+    cleanup_test(test_name);
+
+    let kinds = TomlKindsSettings {
+        enabled: true,
+        count: -3,
+        scale: 1.5,
+        label: "toml".to_string(),
+        levels: vec![1, 2, 3],
+        resolution: TomlResolution {
+            width: 1920,
+            height: 1080,
+        },
+        mode: TomlWindowMode::Fullscreen { monitor: 1 },
+        slots: [(0, "sword".to_string()), (7, "shield".to_string())].into(),
+        // `None` while the default is `Some` has to be stored as well
+        limit: None,
+        optional_levels: vec![Some(1), None],
+        pair: (None, 2),
+    };
+    let mut app = toml_app(test_name);
+    app.world_mut().resource_mut::<TestSettings>().value = 7;
+    *app.world_mut().resource_mut::<TomlKindsSettings>() = kinds.clone();
+    app.update();
+
+    let settings_file = get_test_path(test_name).join("TestSettings.toml");
+    let content = fs::read_to_string(&settings_file).unwrap();
+    assert!(content.contains("[testsettings]\nvalue = 7\n"));
+
+    let app = toml_app(test_name);
+    assert_eq!(app.world().resource::<TestSettings>().value, 7);
+    assert_eq!(*app.world().resource::<TomlKindsSettings>(), kinds);
+
+    cleanup_test(test_name);
+}
+
+#[test]
+fn test_toml_hand_written_file_with_comments() {
+    let test_name = "test_toml_hand_written_file_with_comments";
+    cleanup_test(test_name);
+    let settings_file = get_test_path(test_name).join("TestSettings.toml");
+    fs::create_dir_all(get_test_path(test_name)).unwrap();
+    fs::write(
+        &settings_file,
+        r#"# Edited by hand
+version = "1.0.0"
+
+[testsettings]
+name = "edited" # trailing comment
+value = 3
+
+[graphicssettings]
+quality = 4
+"#,
+    )
+    .unwrap();
+
+    let app = toml_app(test_name);
+    let settings = app.world().resource::<TestSettings>();
+    assert_eq!(settings.value, 3);
+    assert_eq!(settings.name, "edited");
+    assert_eq!(app.world().resource::<GraphicsSettings>().quality, 4);
+
+    cleanup_test(test_name);
+}
+
+fn chained_from_v1(
+    _from: &Version,
+    _range: &semver::VersionReq,
+    delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    // Version 2 stores the volume as a fraction instead of a percentage
+    let volume = delta["volume"].as_f64().unwrap_or(100.0);
+    delta["volume"] = (volume / 100.0).into();
+    Ok(true)
+}
+
+fn chained_from_v2(
+    _from: &Version,
+    _range: &semver::VersionReq,
+    delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    // Version 3 renamed "volume" to "master"
+    if let Some(volume) = delta.as_object_mut().unwrap().remove("volume") {
+        delta["master"] = volume;
+    }
+    Ok(true)
+}
+
+fn chained_from_v3(
+    _from: &Version,
+    _range: &semver::VersionReq,
+    delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    // Version 4 made the master volume go up to 2
+    let master = delta["master"].as_f64().unwrap_or(1.0);
+    delta["master"] = (master * 2.0).into();
+    Ok(true)
+}
+
+fn chained_migrations() -> Vec<(semver::VersionReq, bevy_settings::VersionedMigrationFn)> {
+    vec![
+        (
+            semver::VersionReq::parse(">=3.0.0, <4.0.0").unwrap(),
+            chained_from_v3,
+        ),
+        (
+            semver::VersionReq::parse(">=1.0.0, <2.0.0").unwrap(),
+            chained_from_v1,
+        ),
+        (
+            semver::VersionReq::parse(">=2.0.0, <3.0.0").unwrap(),
+            chained_from_v2,
+        ),
+    ]
+}
+
+fn unreachable_migration(
+    _from: &Version,
+    _delta: &mut serde_json::Value,
+) -> Result<bool, SettingsError> {
+    panic!("the versioned migrations should have been used");
+}
+
+#[derive(Settings, Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[settings(migrations = "chained_migrations")]
+struct ChainedSettings {
+    master: f64,
+}
+
+#[test]
+fn test_versioned_migrations_run_as_chain() {
+    let test_name = "test_versioned_migrations_run_as_chain";
+    cleanup_test(test_name);
+    write_settings_file(
+        test_name,
+        r#"{ "_versions": { "chainedsettings": "1.3.0" }, "chainedsettings": { "volume": 40 } }"#,
+    );
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(
+        SettingsPlugin::new("TestSettings")
+            .format(SerializationFormat::Json)
+            .with_base_path(get_test_path(test_name).to_str().unwrap())
+            .register_with_version::<ChainedSettings>("4.0.0", unreachable_migration),
+    );
+    app.update();
+
+    assert_eq!(app.world().resource::<ChainedSettings>().master, 0.8);
+    let file = read_settings_file(test_name);
+    assert_eq!(file["_versions"]["chainedsettings"], "4.0.0");
+    assert_eq!(
+        file["chainedsettings"],
+        serde_json::json!({ "master": 0.8 })
+    );
+
     cleanup_test(test_name);
 }
@@ -1,37 +1,510 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, LitStr, Type};
 
 /// Derive macro for Settings trait
 ///
-/// This macro implements the Settings trait for a struct, enabling it to be:
+/// This macro implements the Settings trait for a struct or enum, enabling it to be:
 /// - Used as a Bevy resource
 /// - Serialized/deserialized to JSON or binary format
 /// - Managed with default values and delta persistence
 ///
+/// # Attributes
+/// - `#[settings(display_name = "Audio")]` - human-readable name returned by `display_name()`
+/// - `#[settings(schema)]` - generate a basic JSON Schema from the field types, returned by
+///   `schema()`
+/// - `#[settings(migrations = "path::to::fn")]` -
+///   `fn() -> Vec<(VersionReq, VersionedMigrationFn)>`
+///   returning the migrations for ranges of stored versions, returned by `migrations()`
+///
+/// # Field attributes
+/// - `#[settings(added_in = "2.0.0")]` - the field was added in this schema version, its default
+///   value is inserted into sections saved by older versions by `migrate_fields()`
+/// - `#[settings(removed_in = "3.0.0")]` - the field was removed in this schema version, it is
+///   deleted from sections saved by older versions. Combine with `#[serde(skip)]`
+/// - `#[settings(renamed_from = "old_name", in_version = "2.0.0")]` - the field was renamed in
+///   this schema version, `old_name` is renamed in sections saved by older versions
+/// - `#[settings(big_int)]` - the field is stored as a string by
+///   `#[serde(with = "bevy_settings::big_int")]`, which the generated schema reflects
+/// - `#[settings(duration_format = "human")]` - the `Duration` field is stored as a string like
+///   `"500ms"` by `#[serde(with = "bevy_settings::human_duration")]`, which the generated schema
+///   reflects
+/// - `#[settings(set)]` - the field is a set, such as a `HashSet`, whose delta is stored as the
+///   added and removed elements, returned by `set_fields()`
+/// - `#[settings(always_persist)]` - the field is stored even if it equals its default value,
+///   returned by `always_persist_fields()`. `#[settings(skip_delta = true)]` is the same, while
+///   `skip_delta = false` is the default behavior
+/// - `#[settings(secret)]` - the value of the field, like an API key, is replaced with
+///   `"[REDACTED]"` in log output, returned by `secret_fields()`
+///
+/// Fields with `#[serde(alias = "old_name")]` are listed by `serde_aliases()`, so stored
+/// settings using the old name are rewritten to the current one.
+///
 /// # Example
 /// ```ignore
 /// use bevy_settings::Settings;
 /// use serde::{Deserialize, Serialize};
 ///
 /// #[derive(Settings, Serialize, Deserialize, Default, Clone)]
+/// #[settings(display_name = "Game")]
 /// struct GameSettings {
 ///     volume: f32,
 ///     resolution: (u32, u32),
+///     #[settings(added_in = "1.1.0")]
+///     vsync: bool,
 /// }
 /// ```
-#[proc_macro_derive(Settings)]
+#[proc_macro_derive(Settings, attributes(settings))]
 pub fn derive_settings(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
 
+    let attrs = match SettingsAttrs::parse(&input) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let display_name = attrs.display_name.map(|display_name| {
+        quote! {
+            fn display_name() -> &'static str {
+                #display_name
+            }
+        }
+    });
+
+    let schema = if attrs.schema {
+        match generate_schema(&input) {
+            Ok(schema) => Some(schema),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        None
+    };
+
+    let migrations = attrs.migrations.map(|path| {
+        quote! {
+            fn migrations() -> Vec<(
+                bevy_settings::semver::VersionReq,
+                bevy_settings::VersionedMigrationFn,
+            )> {
+                #path()
+            }
+        }
+    });
+
+    let migrate_fields = match generate_migrate_fields(&input) {
+        Ok(migrate_fields) => migrate_fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let set_fields =
+        generate_field_list(&input, quote!(set_fields), |field| has_flag(field, "set"));
+    let always_persist_fields =
+        generate_field_list(&input, quote!(always_persist_fields), is_always_persisted);
+    let secret_fields = generate_field_list(&input, quote!(secret_fields), |field| {
+        has_flag(field, "secret")
+    });
+
+    let serde_aliases = match generate_serde_aliases(&input) {
+        Ok(serde_aliases) => serde_aliases,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let expanded = quote! {
         impl bevy_settings::Settings for #name {
             fn type_name() -> &'static str {
                 stringify!(#name)
             }
+
+            #display_name
+
+            #schema
+
+            #migrate_fields
+
+            #migrations
+
+            #set_fields
+
+            #always_persist_fields
+
+            #secret_fields
+
+            #serde_aliases
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Container-level `#[settings(...)]` attributes
+#[derive(Default)]
+struct SettingsAttrs {
+    display_name: Option<LitStr>,
+    schema: bool,
+    /// Function returning the versioned migrations
+    migrations: Option<syn::ExprPath>,
+}
+
+impl SettingsAttrs {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("settings")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("display_name") {
+                    attrs.display_name = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("schema") {
+                    attrs.schema = true;
+                    Ok(())
+                } else if meta.path.is_ident("migrations") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    attrs.migrations = Some(path.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported settings attribute"))
+                }
+            })?;
+        }
+
+        Ok(attrs)
+    }
+}
+
+/// Generate a `schema()` implementation describing the JSON type of each named field
+///
+/// No field is required, since the stored delta only contains fields that differ from the
+/// defaults. Fields whose type has no obvious JSON equivalent accept any value.
+fn generate_schema(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[settings(schema)] requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[settings(schema)] can only be used on structs",
+            ))
+        }
+    };
+
+    let properties = fields.iter().map(|field| {
+        let name = field
+            .ident
+            .as_ref()
+            .map(|ident| ident.to_string().trim_start_matches("r#").to_string());
+        let property = if has_flag(field, "set") {
+            // The delta of a set is an object with the added and removed elements
+            quote! { bevy_settings::serde_json::json!({ "type": ["array", "object"] }) }
+        } else {
+            match (has_flag(field, "big_int") || has_flag(field, "duration_format"))
+                .then_some("string")
+                .or_else(|| json_type(&field.ty))
+            {
+                Some(json_type) => {
+                    quote! { bevy_settings::serde_json::json!({ "type": #json_type }) }
+                }
+                None => quote! { bevy_settings::serde_json::json!({}) },
+            }
+        };
+        quote! { properties.insert(#name.to_string(), #property); }
+    });
+
+    Ok(quote! {
+        fn schema() -> Option<bevy_settings::serde_json::Value> {
+            let mut properties = bevy_settings::serde_json::Map::new();
+            #(#properties)*
+            Some(bevy_settings::serde_json::json!({
+                "type": "object",
+                "properties": properties,
+            }))
+        }
+    })
+}
+
+/// Generate a `migrate_fields()` implementation from the `#[settings(...)]` field attributes
+///
+/// Returns `None` if no field has a migration attribute, so the default implementation is used.
+/// Renames run first, so a renamed field that is also `added_in` isn't inserted twice, followed
+/// by removals and insertions.
+fn generate_migrate_fields(input: &DeriveInput) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    let Data::Struct(data) = &input.data else {
+        return Ok(None);
+    };
+
+    let mut renames = Vec::new();
+    let mut removals = Vec::new();
+    let mut insertions = Vec::new();
+    for field in &data.fields {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("settings")) {
+            let mut renamed_from: Option<LitStr> = None;
+            let mut in_version = None;
+            attr.parse_nested_meta(|meta| {
+                let Some(ident) = &field.ident else {
+                    return Err(meta.error("field attributes require a named field"));
+                };
+                let key = ident.to_string().trim_start_matches("r#").to_string();
+
+                if meta.path.is_ident("added_in") {
+                    let version = parse_version(meta.value()?.parse()?)?;
+                    insertions.push(quote! {
+                        if *from < bevy_settings::semver::Version::parse(#version).unwrap()
+                            && delta.get(#key).is_none()
+                        {
+                            if let Ok(value) = bevy_settings::serde_json::to_value(&defaults.#ident) {
+                                delta[#key] = value;
+                                changed = true;
+                            }
+                        }
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("removed_in") {
+                    let version = parse_version(meta.value()?.parse()?)?;
+                    removals.push(quote! {
+                        if *from < bevy_settings::semver::Version::parse(#version).unwrap() {
+                            changed |= bevy_settings::MigrationHelpers::remove_field(delta, #key);
+                        }
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("big_int")
+                    || meta.path.is_ident("set")
+                    || meta.path.is_ident("always_persist")
+                    || meta.path.is_ident("secret")
+                {
+                    // Handled by `generate_schema` and `generate_field_list`
+                    Ok(())
+                } else if meta.path.is_ident("skip_delta") {
+                    meta.value()?.parse::<syn::LitBool>()?;
+                    Ok(())
+                } else if meta.path.is_ident("duration_format") {
+                    let format: LitStr = meta.value()?.parse()?;
+                    if format.value() != "human" {
+                        return Err(syn::Error::new_spanned(
+                            format,
+                            "unsupported duration format, expected \"human\"",
+                        ));
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("renamed_from") {
+                    renamed_from = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("in_version") {
+                    in_version = Some(parse_version(meta.value()?.parse()?)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported settings field attribute"))
+                }
+            })?;
+
+            match (renamed_from, in_version, &field.ident) {
+                (Some(old), Some(version), Some(ident)) => {
+                    let key = ident.to_string().trim_start_matches("r#").to_string();
+                    renames.push(quote! {
+                        if *from < bevy_settings::semver::Version::parse(#version).unwrap() {
+                            changed |= bevy_settings::MigrationHelpers::rename_field(delta, #old, #key);
+                        }
+                    });
+                }
+                (Some(_), None, _) => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "renamed_from requires in_version = \"...\"",
+                    ))
+                }
+                (None, Some(_), _) => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "in_version can only be used with renamed_from",
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if renames.is_empty() && removals.is_empty() && insertions.is_empty() {
+        return Ok(None);
+    }
+
+    let defaults = (!insertions.is_empty()).then(|| {
+        quote! { let defaults = <Self as Default>::default(); }
+    });
+
+    Ok(Some(quote! {
+        fn migrate_fields(
+            from: &bevy_settings::semver::Version,
+            delta: &mut bevy_settings::serde_json::Value,
+        ) -> bool {
+            if !delta.is_object() {
+                return false;
+            }
+            #defaults
+            let mut changed = false;
+            #(#renames)*
+            #(#removals)*
+            #(#insertions)*
+            changed
+        }
+    }))
+}
+
+/// Check that a version attribute is a valid semver version
+fn parse_version(version: LitStr) -> syn::Result<LitStr> {
+    match semver::Version::parse(&version.value()) {
+        Ok(_) => Ok(version),
+        Err(err) => Err(syn::Error::new_spanned(
+            &version,
+            format!("invalid semver version: {}", err),
+        )),
+    }
+}
+
+/// Generate an implementation of `function`, like `set_fields()`, listing the names of the
+/// fields matching `filter`
+///
+/// Returns `None` if no field matches, so the default implementation is used.
+fn generate_field_list(
+    input: &DeriveInput,
+    function: proc_macro2::TokenStream,
+    filter: impl Fn(&Field) -> bool,
+) -> Option<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return None;
+    };
+
+    let names: Vec<String> = data
+        .fields
+        .iter()
+        .filter(|field| filter(field))
+        .filter_map(|field| field.ident.as_ref())
+        .map(|ident| ident.to_string().trim_start_matches("r#").to_string())
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        fn #function() -> &'static [&'static str] {
+            &[#(#names),*]
+        }
+    })
+}
+
+/// Generate a `serde_aliases()` implementation from the `#[serde(alias = "...")]` field attributes
+///
+/// Returns `None` if no field has an alias, so the default implementation is used. Other serde
+/// attributes are skipped, except `rename`, which changes the name the alias maps to.
+fn generate_serde_aliases(input: &DeriveInput) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    let Data::Struct(data) = &input.data else {
+        return Ok(None);
+    };
+
+    let mut pairs = Vec::new();
+    for field in &data.fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let mut key = ident.to_string().trim_start_matches("r#").to_string();
+        let mut aliases = Vec::new();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("alias") {
+                    aliases.push(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|nested| {
+                        if nested.input.peek(syn::Token![=]) {
+                            nested.value()?.parse::<syn::Expr>()?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            })?;
+        }
+        pairs.extend(aliases.into_iter().map(|alias| quote! { (#alias, #key) }));
+    }
+    if pairs.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(quote! {
+        fn serde_aliases() -> &'static [(&'static str, &'static str)] {
+            &[#(#pairs),*]
+        }
+    }))
+}
+
+/// Check whether a field is marked with a flag like `#[settings(big_int)]`
+fn has_flag(field: &Field, flag: &str) -> bool {
+    field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("settings"))
+        .any(|attr| {
+            let mut found = false;
+            // Other attributes are validated by `generate_migrate_fields`
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident(flag);
+                if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Lit>()?;
+                }
+                Ok(())
+            });
+            found
+        })
+}
+
+/// Check whether a field is marked with `#[settings(always_persist)]` or
+/// `#[settings(skip_delta = true)]`
+fn is_always_persisted(field: &Field) -> bool {
+    has_flag(field, "always_persist")
+        || field
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("settings"))
+            .any(|attr| {
+                let mut skip_delta = false;
+                let _ = attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("skip_delta") {
+                        skip_delta = meta.value()?.parse::<syn::LitBool>()?.value;
+                    } else if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<syn::Lit>()?;
+                    }
+                    Ok(())
+                });
+                skip_delta
+            })
+}
+
+/// Map a Rust type to the name of its JSON Schema type, if there is an obvious one
+fn json_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Array(_) | Type::Slice(_) | Type::Tuple(_) => Some("array"),
+        Type::Reference(reference) => json_type(&reference.elem),
+        Type::Path(path) => {
+            let ident = path.path.segments.last()?.ident.to_string();
+            match ident.as_str() {
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize" => Some("integer"),
+                "f32" | "f64" => Some("number"),
+                "bool" => Some("boolean"),
+                "String" | "str" | "char" => Some("string"),
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => Some("array"),
+                "HashMap" | "BTreeMap" => Some("object"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}